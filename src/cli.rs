@@ -1,10 +1,164 @@
-pub fn validate_time(s: &str) -> Result<u64, String> {
-    let time: u64 = s
-        .parse()
-        .map_err(|_| format!("`{}` is not a valid number", s))?;
-    if time == 0 || time > 1440 {
-        Err("Time must be between 1 and 1440 minutes".to_string())
+use chrono::{Days, NaiveDate};
+use clap::{Args, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::duration::Seconds;
+use crate::settings;
+
+pub const MAX_TAG_LENGTH: usize = 32;
+
+pub fn validate_tag(s: &str) -> Result<String, String> {
+    let tag = s.trim();
+    if tag.is_empty() {
+        Err("Tag must not be empty".to_string())
+    } else if tag.chars().count() > MAX_TAG_LENGTH {
+        Err(format!("Tag must be at most {} characters", MAX_TAG_LENGTH))
     } else {
-        Ok(time)
+        Ok(tag.to_string())
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print session stats without starting the timer (last 7 days by default)
+    Stats(StatsArgs),
+    /// Export session history as CSV, JSON Lines or iCalendar (all of it by default)
+    Export(ExportArgs),
+    /// Merge a stats.toml or history.toml from another machine into this one
+    #[command(alias = "merge")]
+    Import(ImportArgs),
+    /// Inspect the effective configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Add time to the countdown running in another terminal
+    Extend(AdjustArgs),
+    /// Take time off the countdown running in another terminal
+    Shorten(AdjustArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AdjustArgs {
+    /// How much time, e.g. 5m or 90s; plain numbers are minutes
+    #[arg(default_value = "5m", value_parser = settings::parse_duration)]
+    pub duration: Seconds,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print each setting's effective value and where it came from
+    Show,
+}
+
+#[derive(Args, Debug)]
+pub struct PeriodArgs {
+    /// Only today
+    #[arg(long, group = "period")]
+    pub today: bool,
+    /// The last 7 days
+    #[arg(long, group = "period")]
+    pub week: bool,
+    /// The last 30 days
+    #[arg(long, group = "period")]
+    pub month: bool,
+    /// An inclusive date range, e.g. 2025-01-01..2025-01-31
+    #[arg(long, group = "period", value_parser = parse_date_range)]
+    pub range: Option<DateRange>,
+}
+
+impl PeriodArgs {
+    /// The selected period, or `None` when no flag was given.
+    pub fn date_range(&self, today: NaiveDate) -> Option<DateRange> {
+        if let Some(range) = self.range {
+            Some(range)
+        } else if self.today {
+            Some(DateRange::last_days(today, 1))
+        } else if self.week {
+            Some(DateRange::last_days(today, 7))
+        } else if self.month {
+            Some(DateRange::last_days(today, 30))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    pub period: PeriodArgs,
+    /// Print JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl StatsArgs {
+    pub fn date_range(&self, today: NaiveDate) -> DateRange {
+        self.period
+            .date_range(today)
+            .unwrap_or_else(|| DateRange::last_days(today, 7))
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[arg(short, long, value_enum)]
+    pub format: ExportFormat,
+    #[command(flatten)]
+    pub period: PeriodArgs,
+    /// Write to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// Path to the other machine's stats.toml or history.toml
+    pub path: PathBuf,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Ics,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    pub fn last_days(end: NaiveDate, days: u64) -> Self {
+        let start = end
+            .checked_sub_days(Days::new(days.saturating_sub(1)))
+            .unwrap_or(end);
+        Self { start, end }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    pub fn days(&self) -> u64 {
+        (self.end - self.start).num_days() as u64 + 1
+    }
+}
+
+pub fn parse_date_range(s: &str) -> Result<DateRange, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("`{}` is not a range, expected START..END", s))?;
+    let parse = |d: &str| {
+        d.trim()
+            .parse::<NaiveDate>()
+            .map_err(|_| format!("`{}` is not a valid date, expected YYYY-MM-DD", d))
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start > end {
+        Err(format!("Range start {} is after its end {}", start, end))
+    } else {
+        Ok(DateRange { start, end })
     }
 }
@@ -1,10 +1,26 @@
-use crate::{settings};
-use color_eyre::Result;
+use crate::settings;
+use chrono::Local;
+use color_eyre::{Result, eyre::eyre};
 use dirs::config_dir;
-use std::{fs, path::PathBuf};
+use serde::de::DeserializeOwned;
+use std::{
+    fs::{self, File, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 pub struct Config;
 
+/// How long `Config::lock` waits for another process before giving up.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(2);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Holds the advisory lock on the config directory until dropped.
+pub struct ConfigLock {
+    _file: File,
+}
+
 impl Config {
     pub fn get_config_dir() -> Result<PathBuf> {
         let config_dir = config_dir()
@@ -15,25 +31,206 @@ impl Config {
         Ok(app_config_dir)
     }
 
+    /// Waits for other fokus processes to let go of the config directory,
+    /// but only for `LOCK_TIMEOUT`: the callers run on the async runtime and
+    /// must not block it for long. Never call this while already holding the
+    /// lock, it is not re-entrant.
+    pub fn lock() -> Result<ConfigLock> {
+        let file = File::create(Self::get_config_dir()?.join(".lock"))?;
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(ConfigLock { _file: file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => {
+                    std::thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(eyre!(
+                        "Another fokus process has kept the config directory locked for over {}s",
+                        LOCK_TIMEOUT.as_secs()
+                    ));
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Writes to a temporary file and renames it over `path`, so a crash
+    /// mid-write leaves either the old or the new contents behind.
+    pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| color_eyre::eyre::eyre!("{} is not a file", path.display()))?;
+        let tmp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Copies an unreadable file to `<name>.corrupt-<timestamp>` next to it,
+    /// so whatever replaces it is never the only copy.
+    pub fn backup_corrupt(path: &Path) -> Result<PathBuf> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| eyre!("{} is not a file", path.display()))?;
+        let backup_path = path.with_file_name(format!(
+            "{}.corrupt-{}",
+            file_name.to_string_lossy(),
+            Local::now().format("%Y%m%d%H%M%S")
+        ));
+        fs::copy(path, &backup_path)?;
+        Ok(backup_path)
+    }
+
+    /// Backs up `path` if it exists but does not parse as `T`. Run before
+    /// overwriting a file we may never have managed to read.
+    pub fn backup_if_unreadable<T: DeserializeOwned>(path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let readable = fs::read_to_string(path)
+            .ok()
+            .is_some_and(|contents| toml::from_str::<T>(&contents).is_ok());
+        if !readable {
+            Self::backup_corrupt(path)?;
+        }
+        Ok(())
+    }
+
     fn get_settings_path() -> Result<PathBuf> {
         Ok(Self::get_config_dir()?.join("settings.toml"))
     }
 
-    pub fn load_settings() -> Result<settings::Settings> {
+    /// For the headless commands, validation warnings go to stderr.
+    pub fn load_settings() -> Result<settings::SettingsFile> {
+        let (settings_file, warnings) = Self::load_settings_with_warnings()?;
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(settings_file)
+    }
+
+    /// `settings.toml` with its profiles, without environment or CLI
+    /// overrides. This is what the settings screen edits and saves. Values
+    /// out of range are dropped, the warnings say which.
+    pub fn load_settings_with_warnings() -> Result<(settings::SettingsFile, Vec<String>)> {
         let config_path = Self::get_settings_path()?;
         if config_path.exists() {
-            let config_str = fs::read_to_string(config_path)?;
-            let settings: settings::Settings = toml::from_str(&config_str)?;
-            Ok(settings)
+            let config_str = fs::read_to_string(&config_path)?;
+            match toml::from_str::<settings::SettingsFile>(&config_str) {
+                Ok(mut settings_file) => {
+                    let warnings = settings_file.drop_invalid();
+                    Ok((settings_file, warnings))
+                }
+                Err(e) => {
+                    let backup_path = Self::backup_corrupt(&config_path)?;
+                    Err(eyre!(
+                        "settings.toml is invalid ({}), a copy was saved to {}",
+                        e.message(),
+                        backup_path.display()
+                    ))
+                }
+            }
         } else {
-            Ok(settings::Settings::default())
+            Ok((settings::SettingsFile::default(), Vec::new()))
         }
     }
 
-    pub fn save_settings(settings: &settings::Settings) -> Result<()> {
-        let config_path = Self::get_settings_path()?;
-        let config_str = toml::to_string_pretty(settings)?;
-        fs::write(config_path, config_str)?;
+    pub fn load_env_layer() -> Result<settings::SettingsLayer> {
+        let (layer, warnings) = Self::load_env_layer_with_warnings()?;
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(layer)
+    }
+
+    /// Reads `FOKUS_<SETTING>` variables, e.g. `FOKUS_WORKING_TIME=50`. Each
+    /// variable is read on its own, so one that does not parse is ignored
+    /// with a warning instead of discarding the others.
+    pub fn load_env_layer_with_warnings() -> Result<(settings::SettingsLayer, Vec<String>)> {
+        let mut layer = settings::SettingsLayer::default();
+        let mut warnings = Vec::new();
+        for (name, _) in settings::Settings::default().fields() {
+            let var = Self::env_var_name(&name);
+            let Ok(raw) = std::env::var(&var) else {
+                continue;
+            };
+            let value = toml::from_str::<toml::Table>(&format!("value = {}", raw))
+                .ok()
+                .and_then(|mut parsed| parsed.remove("value"))
+                .unwrap_or(toml::Value::String(raw.clone()));
+            let table = toml::Table::from_iter([(name, value)]);
+            match table.try_into::<settings::SettingsLayer>() {
+                Ok(variable) => layer.merge(&variable),
+                Err(e) => warnings.push(format!("Ignoring {}: {}", var, e.message())),
+            }
+        }
+        warnings.extend(layer.drop_invalid("the environment"));
+        Ok((layer, warnings))
+    }
+
+    /// Prints every setting with the layer it was taken from. Later layers win:
+    /// default, settings.toml, active profile, environment, CLI flag.
+    pub fn show(cli_layer: &settings::SettingsLayer, cli_profile: Option<&str>) -> Result<()> {
+        let mut settings_file = Self::load_settings()?;
+        if let Some(profile) = cli_profile {
+            settings_file
+                .set_cli_profile(profile.to_string())
+                .map_err(|e| eyre!(e))?;
+        }
+        let env_layer = Self::load_env_layer()?;
+        let profile_layer = settings_file
+            .active_profile_layer()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut settings = settings_file.resolve();
+        for layer in [&env_layer, cli_layer] {
+            layer.apply_to(&mut settings);
+        }
+
+        println!(
+            "Active profile: {}",
+            settings_file.active_profile().unwrap_or("none")
+        );
+        println!();
+        println!("{:<28} {:>8}  Source", "Setting", "Value");
+        for (name, value) in settings.fields() {
+            let source = if cli_layer.is_set(&name) {
+                "cli flag".to_string()
+            } else if env_layer.is_set(&name) {
+                Self::env_var_name(&name)
+            } else if profile_layer.is_set(&name) {
+                format!(
+                    "profile `{}`",
+                    settings_file.active_profile().unwrap_or_default()
+                )
+            } else if settings_file.base.is_set(&name) {
+                Self::get_settings_path()?.display().to_string()
+            } else {
+                "default".to_string()
+            };
+            println!("{:<28} {:>8}  {}", name, value, source);
+        }
         Ok(())
     }
+
+    pub fn env_var_name(setting: &str) -> String {
+        format!("FOKUS_{}", setting.to_uppercase())
+    }
+
+    pub fn save_settings(settings_file: &settings::SettingsFile) -> Result<()> {
+        let config_path = Self::get_settings_path()?;
+        let config_str = toml::to_string_pretty(settings_file)?;
+        let _lock = Self::lock()?;
+        Self::backup_if_unreadable::<settings::SettingsFile>(&config_path)?;
+        Self::write_atomic(&config_path, &config_str)
+    }
 }
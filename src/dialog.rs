@@ -0,0 +1,87 @@
+use crossterm::event::KeyCode;
+
+/// A modal question drawn over the current screen, answered with a single
+/// key. `A` is whatever the caller wants back for each answer.
+#[derive(Debug, Clone)]
+pub struct Dialog<A> {
+    pub title: String,
+    pub message: String,
+    pub choices: Vec<Choice<A>>,
+    /// The keys of the choices Enter and Esc stand for. Without one the key
+    /// does nothing.
+    pub enter: Option<char>,
+    pub esc: Option<char>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Choice<A> {
+    pub key: char,
+    pub label: String,
+    pub action: A,
+}
+
+impl<A: Copy> Dialog<A> {
+    pub fn new(title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            choices: Vec::new(),
+            enter: None,
+            esc: None,
+        }
+    }
+
+    pub fn choice(mut self, key: char, label: impl Into<String>, action: A) -> Self {
+        self.choices.push(Choice {
+            key,
+            label: label.into(),
+            action,
+        });
+        self
+    }
+
+    /// Makes Enter pick the choice with this key.
+    pub fn on_enter(mut self, key: char) -> Self {
+        self.enter = Some(key);
+        self
+    }
+
+    /// Makes Esc pick the choice with this key, which should be the one that
+    /// loses nothing.
+    pub fn on_esc(mut self, key: char) -> Self {
+        self.esc = Some(key);
+        self
+    }
+
+    /// The answer for a key press.
+    pub fn answer(&self, key: KeyCode) -> Option<A> {
+        let key = match key {
+            KeyCode::Enter => self.enter?,
+            KeyCode::Esc => self.esc?,
+            KeyCode::Char(c) => c,
+            _ => return None,
+        };
+        self.choices
+            .iter()
+            .find(|choice| choice.key == key)
+            .map(|choice| choice.action)
+    }
+
+    /// The choices as a hint line, e.g. `'a' abandon | 'c'/Esc cancel`.
+    pub fn hint(&self) -> String {
+        self.choices
+            .iter()
+            .map(|choice| {
+                let mut keys = format!("'{}'", choice.key);
+                if self.enter == Some(choice.key) {
+                    keys.push_str("/Enter");
+                }
+                if self.esc == Some(choice.key) {
+                    keys.push_str("/Esc");
+                }
+                format!("{} {}", keys, choice.label)
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
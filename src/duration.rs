@@ -0,0 +1,203 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt, str::FromStr};
+
+/// A phase length with second granularity. Written to `settings.toml` as a
+/// string like `"25m"` or `"1h30m"`; bare numbers are read as minutes so older
+/// files keep working.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Seconds(pub u64);
+
+impl Seconds {
+    pub fn from_minutes(minutes: u64) -> Self {
+        Seconds(minutes * 60)
+    }
+
+    /// `None` when the minutes do not fit in seconds.
+    pub fn checked_from_minutes(minutes: u64) -> Option<Self> {
+        minutes.checked_mul(60).map(Seconds)
+    }
+
+    pub fn as_secs(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (hours, minutes, seconds) = (self.0 / 3600, self.0 / 60 % 60, self.0 % 60);
+        let mut out = String::new();
+        if hours > 0 {
+            out.push_str(&format!("{}h", hours));
+        }
+        if minutes > 0 {
+            out.push_str(&format!("{}m", minutes));
+        }
+        if seconds > 0 || out.is_empty() {
+            out.push_str(&format!("{}s", seconds));
+        }
+        f.write_str(&out)
+    }
+}
+
+impl FromStr for Seconds {
+    type Err = String;
+
+    /// Accepts `25m`, `90s`, `1h30m`, `1.5h` and bare minutes such as `25`
+    /// or `1.5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if input.is_empty() {
+            return Err("Duration must not be empty".to_string());
+        }
+        let invalid = || format!("`{}` is not a valid duration, try 25m, 90s or 1h30m", s);
+
+        if let Ok(minutes) = input.parse::<f64>() {
+            return to_seconds(minutes * 60.0).ok_or_else(invalid);
+        }
+
+        let mut total = 0.0;
+        let mut number = String::new();
+        for c in input.chars() {
+            match c {
+                '0'..='9' | '.' => number.push(c),
+                'h' | 'm' | 's' => {
+                    let value: f64 = number.parse().map_err(|_| invalid())?;
+                    total += value
+                        * match c {
+                            'h' => 3600.0,
+                            'm' => 60.0,
+                            _ => 1.0,
+                        };
+                    number.clear();
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if !number.is_empty() {
+            return Err(invalid());
+        }
+        to_seconds(total).ok_or_else(invalid)
+    }
+}
+
+fn to_seconds(secs: f64) -> Option<Seconds> {
+    // `as` would saturate anything too large instead of rejecting it
+    (secs.is_finite() && secs >= 0.0 && secs.round() < u64::MAX as f64)
+        .then(|| Seconds(secs.round() as u64))
+}
+
+impl Serialize for Seconds {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Seconds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SecondsVisitor;
+
+        impl de::Visitor<'_> for SecondsVisitor {
+            type Value = Seconds;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("minutes or a duration string like \"25m\" or \"90s\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, minutes: u64) -> Result<Seconds, E> {
+                Seconds::checked_from_minutes(minutes)
+                    .ok_or_else(|| E::custom("duration is too long"))
+            }
+
+            fn visit_i64<E: de::Error>(self, minutes: i64) -> Result<Seconds, E> {
+                let minutes = u64::try_from(minutes)
+                    .map_err(|_| E::custom("duration must not be negative"))?;
+                self.visit_u64(minutes)
+            }
+
+            fn visit_f64<E: de::Error>(self, minutes: f64) -> Result<Seconds, E> {
+                to_seconds(minutes * 60.0).ok_or_else(|| E::custom("invalid duration"))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Seconds, E> {
+                s.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(SecondsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Doc {
+        duration: Seconds,
+    }
+
+    fn deserialize(value: &str) -> Result<Seconds, toml::de::Error> {
+        toml::from_str::<Doc>(&format!("duration = {}", value)).map(|doc| doc.duration)
+    }
+
+    #[test]
+    fn parses_units_and_bare_minutes() {
+        assert_eq!("25m".parse(), Ok(Seconds(1500)));
+        assert_eq!("90s".parse(), Ok(Seconds(90)));
+        assert_eq!("1h30m".parse(), Ok(Seconds(5400)));
+        assert_eq!("1.5h".parse(), Ok(Seconds(5400)));
+        assert_eq!(" 1h 30m ".parse(), Ok(Seconds(5400)));
+        assert_eq!("25".parse(), Ok(Seconds(1500)));
+        assert_eq!("1.5".parse(), Ok(Seconds(90)));
+    }
+
+    #[test]
+    fn rounds_to_whole_seconds() {
+        assert_eq!("0.4s".parse(), Ok(Seconds(0)));
+        assert_eq!("0.6s".parse(), Ok(Seconds(1)));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in ["", "1h30", "-5", "-5m", "inf", "NaN", "5x", "m", "1..5m"] {
+            assert!(input.parse::<Seconds>().is_err(), "{:?} parsed", input);
+        }
+    }
+
+    #[test]
+    fn rejects_durations_that_do_not_fit() {
+        assert!("99999999999999999999".parse::<Seconds>().is_err());
+        assert!("99999999999999999999h".parse::<Seconds>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for secs in [0, 45, 60, 1500, 5400, 5445, 86400] {
+            let shown = Seconds(secs).to_string();
+            assert_eq!(shown.parse(), Ok(Seconds(secs)), "{}", shown);
+        }
+    }
+
+    #[test]
+    fn deserializes_numbers_as_minutes() {
+        assert_eq!(deserialize("25").unwrap(), Seconds(1500));
+        assert_eq!(deserialize("1.5").unwrap(), Seconds(90));
+        assert_eq!(deserialize("\"1h30m\"").unwrap(), Seconds(5400));
+    }
+
+    #[test]
+    fn deserializing_edge_cases_fails_cleanly() {
+        for value in [
+            "-5",
+            "-0.5",
+            "inf",
+            "nan",
+            "9223372036854775807",
+            "1e300",
+            "\"1h30\"",
+            "\"inf\"",
+        ] {
+            assert!(deserialize(value).is_err(), "{} deserialized", value);
+        }
+    }
+}
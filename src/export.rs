@@ -0,0 +1,267 @@
+use chrono::{Local, Utc};
+use color_eyre::Result;
+use std::{fs, io::Write};
+
+use crate::cli::{ExportArgs, ExportFormat};
+use crate::history::{SessionKind, SessionOutcome, SessionRecord};
+use crate::settings::SuspendPolicy;
+use crate::stats::SessionStats;
+
+pub fn export_history(args: &ExportArgs) -> Result<()> {
+    let range = args.period.date_range(Local::now().date_naive());
+    let records: Vec<SessionRecord> = SessionStats::load_records()?
+        .into_iter()
+        .filter(|r| range.is_none_or(|range| range.contains(r.date())))
+        .collect();
+
+    let output = match args.format {
+        ExportFormat::Csv => to_csv(&records),
+        ExportFormat::Jsonl => to_jsonl(&records)?,
+        ExportFormat::Ics => to_ics(&records),
+    };
+
+    match &args.output {
+        Some(path) => fs::write(path, output)?,
+        None => std::io::stdout().write_all(output.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn to_csv(records: &[SessionRecord]) -> String {
+    let mut out = String::from(
+        "id,kind,outcome,started_at,ended_at,planned_secs,focused_secs,pauses,tag,legacy,suspended_secs,suspend_policy,extended_secs,phase\n",
+    );
+    for r in records {
+        let row = [
+            r.id.clone(),
+            kind_name(r.kind).to_string(),
+            outcome_name(r.outcome).to_string(),
+            r.started_at.to_rfc3339(),
+            r.ended_at.to_rfc3339(),
+            r.planned_secs.to_string(),
+            r.focused_secs.to_string(),
+            r.pauses.to_string(),
+            r.tag.clone().unwrap_or_default(),
+            r.legacy.to_string(),
+            r.suspension.map(|s| s.secs.to_string()).unwrap_or_default(),
+            r.suspension
+                .map(|s| suspend_policy_name(s.policy).to_string())
+                .unwrap_or_default(),
+            r.extended_secs.to_string(),
+            r.phase.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_jsonl(records: &[SessionRecord]) -> Result<String> {
+    let mut out = String::new();
+    for record in records {
+        out.push_str(&serde_json::to_string(record)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// One VEVENT per focus block. Legacy records only know their date and
+/// discarded ones were not worked, so both are left out.
+fn to_ics(records: &[SessionRecord]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//fokus//Fokus//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    let worked = records
+        .iter()
+        .filter(|r| r.is_work() && !r.legacy && r.outcome != SessionOutcome::Discarded);
+    for r in worked {
+        let summary = match (&r.tag, &r.phase) {
+            (Some(tag), _) => format!("Fokus: {}", tag),
+            (None, Some(phase)) => phase.clone(),
+            (None, None) => "Fokus session".to_string(),
+        };
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@fokus", r.id),
+            format!("DTSTAMP:{}", stamp),
+            // Stored in local time, written as UTC so calendars place it correctly
+            format!(
+                "DTSTART:{}",
+                r.started_at.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+            ),
+            format!(
+                "DTEND:{}",
+                r.ended_at.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+            ),
+            format!("SUMMARY:{}", ics_text(&summary)),
+            format!(
+                "DESCRIPTION:{}",
+                ics_text(&format!(
+                    "{} | focused {} min of {} | {} pauses",
+                    outcome_name(r.outcome),
+                    r.focused_secs / 60,
+                    r.planned_secs / 60,
+                    r.pauses
+                ))
+            ),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    // iCalendar wants CRLF line endings
+    lines
+        .iter()
+        .map(|line| ics_fold(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+/// Splits a content line into lines of at most 75 octets, continued with a
+/// leading space (RFC 5545 §3.1). Never splits inside a UTF-8 character.
+fn ics_fold(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+fn ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn kind_name(kind: SessionKind) -> &'static str {
+    match kind {
+        SessionKind::Work => "work",
+        SessionKind::ShortBreak => "short_break",
+        SessionKind::LongBreak => "long_break",
+    }
+}
+
+fn suspend_policy_name(policy: SuspendPolicy) -> &'static str {
+    match policy {
+        SuspendPolicy::Pause => "pause",
+        SuspendPolicy::Count => "count",
+        SuspendPolicy::Discard => "discard",
+    }
+}
+
+fn outcome_name(outcome: SessionOutcome) -> &'static str {
+    match outcome {
+        SessionOutcome::Completed => "completed",
+        SessionOutcome::Skipped => "skipped",
+        SessionOutcome::Discarded => "discarded",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(outcome: SessionOutcome, tag: Option<&str>) -> SessionRecord {
+        let started_at = Local::now();
+        SessionRecord {
+            id: format!("{:?}", outcome),
+            kind: SessionKind::Work,
+            outcome,
+            started_at,
+            ended_at: started_at,
+            planned_secs: 1500,
+            focused_secs: 1500,
+            pauses: 0,
+            tag: tag.map(str::to_string),
+            legacy: false,
+            suspension: None,
+            extended_secs: 0,
+            phase: None,
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("code-review"), "code-review");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn ics_text_escapes_special_characters() {
+        assert_eq!(ics_text("plain"), "plain");
+        assert_eq!(ics_text("a,b;c"), "a\\,b\\;c");
+        assert_eq!(ics_text("back\\slash"), "back\\\\slash");
+        assert_eq!(ics_text("two\nlines"), "two\\nlines");
+    }
+
+    #[test]
+    fn short_lines_are_not_folded() {
+        let line = "x".repeat(75);
+        assert_eq!(ics_fold(&line), line);
+    }
+
+    #[test]
+    fn long_lines_fold_at_75_octets() {
+        let folded = ics_fold(&"x".repeat(200));
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        assert_eq!(folded.replace("\r\n ", ""), "x".repeat(200));
+    }
+
+    #[test]
+    fn folding_keeps_multibyte_characters_whole() {
+        let line = format!("SUMMARY:Fokus: {}", "ü".repeat(40));
+        let folded = ics_fold(&line);
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= 75, "{} octets", part.len());
+        }
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn ics_leaves_out_discarded_sessions() {
+        let ics = to_ics(&[
+            record(SessionOutcome::Completed, Some("kept")),
+            record(SessionOutcome::Discarded, Some("slept through")),
+        ]);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("SUMMARY:Fokus: kept"));
+        assert!(!ics.contains("slept through"));
+    }
+
+    #[test]
+    fn ics_lines_stay_within_75_octets() {
+        let tag = "ä".repeat(32);
+        let ics = to_ics(&[record(SessionOutcome::Completed, Some(&tag))]);
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    }
+}
@@ -0,0 +1,388 @@
+use chrono::{DateTime, Local, NaiveDate};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use crate::config::Config;
+use crate::settings::SuspendPolicy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    Completed,
+    Skipped,
+    /// Thrown away, because the machine slept through it or it was abandoned.
+    Discarded,
+}
+
+/// How long the machine slept during a phase and what was done about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suspension {
+    pub secs: u64,
+    pub policy: SuspendPolicy,
+}
+
+/// A single finished (or skipped) phase, as written to `history.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub id: String,
+    pub kind: SessionKind,
+    pub outcome: SessionOutcome,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub planned_secs: u64,
+    /// Seconds the countdown was actually running, pauses excluded.
+    pub focused_secs: u64,
+    pub pauses: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Migrated from the old per-day counter, so only the date is meaningful.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub legacy: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspension: Option<Suspension>,
+    /// Net seconds added to (or taken off) the countdown while it ran.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub extended_secs: i64,
+    /// The phase name from a user-defined cycle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+}
+
+fn is_zero(secs: &i64) -> bool {
+    *secs == 0
+}
+
+impl SessionRecord {
+    pub fn date(&self) -> NaiveDate {
+        self.started_at.date_naive()
+    }
+
+    pub fn is_work(&self) -> bool {
+        self.kind == SessionKind::Work
+    }
+}
+
+/// Tracks the phase currently on the clock until it is turned into a record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveSession {
+    kind: SessionKind,
+    started_at: DateTime<Local>,
+    planned_secs: u64,
+    focused_secs: u64,
+    pauses: u64,
+    tag: Option<String>,
+    suspension: Option<Suspension>,
+    #[serde(default)]
+    extended_secs: i64,
+    #[serde(default)]
+    phase: Option<String>,
+}
+
+impl ActiveSession {
+    pub fn new(kind: SessionKind, planned_secs: u64, tag: Option<String>) -> Self {
+        Self {
+            kind,
+            started_at: Local::now(),
+            planned_secs,
+            focused_secs: 0,
+            pauses: 0,
+            tag,
+            suspension: None,
+            extended_secs: 0,
+            phase: None,
+        }
+    }
+
+    pub fn set_phase(&mut self, phase: Option<String>) {
+        self.phase = phase;
+    }
+
+    pub fn kind(&self) -> SessionKind {
+        self.kind
+    }
+
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag;
+    }
+
+    /// The phase length was edited while it was running.
+    pub fn set_planned(&mut self, secs: u64) {
+        self.planned_secs = secs;
+    }
+
+    pub fn focused_secs(&self) -> u64 {
+        self.focused_secs
+    }
+
+    pub fn add_elapsed(&mut self, secs: u64) {
+        self.focused_secs += secs;
+    }
+
+    pub fn add_pause(&mut self) {
+        self.pauses += 1;
+    }
+
+    pub fn add_extension(&mut self, secs: i64) {
+        self.extended_secs += secs;
+    }
+
+    pub fn extended_secs(&self) -> i64 {
+        self.extended_secs
+    }
+
+    /// The planned length with the `+`/`-` adjustments applied.
+    pub fn length_secs(&self) -> u64 {
+        self.planned_secs.saturating_add_signed(self.extended_secs)
+    }
+
+    /// Sleeping more than once adds up, the latest policy is kept.
+    pub fn add_suspension(&mut self, secs: u64, policy: SuspendPolicy) {
+        let previous = self.suspension.map_or(0, |s| s.secs);
+        self.suspension = Some(Suspension {
+            secs: previous + secs,
+            policy,
+        });
+    }
+
+    pub fn finish(self, outcome: SessionOutcome) -> SessionRecord {
+        SessionRecord {
+            id: new_record_id(self.started_at),
+            kind: self.kind,
+            outcome,
+            started_at: self.started_at,
+            ended_at: Local::now(),
+            planned_secs: self.planned_secs,
+            focused_secs: self.focused_secs,
+            pauses: self.pauses,
+            tag: self.tag,
+            legacy: false,
+            suspension: self.suspension,
+            extended_secs: self.extended_secs,
+            phase: self.phase,
+        }
+    }
+}
+
+fn new_record_id(started_at: DateTime<Local>) -> String {
+    format!(
+        "{:x}-{:x}",
+        started_at.timestamp_nanos_opt().unwrap_or_default(),
+        std::process::id()
+    )
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    #[serde(default)]
+    sessions: Vec<SessionRecord>,
+}
+
+pub struct SessionHistory;
+
+impl SessionHistory {
+    pub fn get_history_path() -> Result<PathBuf> {
+        Ok(Config::get_config_dir()?.join("history.toml"))
+    }
+
+    pub fn exists() -> Result<bool> {
+        Ok(Self::get_history_path()?.exists())
+    }
+
+    pub fn load() -> Result<Vec<SessionRecord>> {
+        let history_path = Self::get_history_path()?;
+        if history_path.exists() {
+            Self::parse(&fs::read_to_string(history_path)?)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Appends records as `[[sessions]]` tables without rewriting the file.
+    /// Callers are expected to hold `Config::lock`.
+    pub fn append(records: &[SessionRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let history_path = Self::get_history_path()?;
+        let chunk = toml::to_string_pretty(&HistoryFile {
+            sessions: records.to_vec(),
+        })?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_path)?;
+        // A single write keeps concurrent appends from interleaving
+        file.write_all(format!("{}\n", chunk).as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Turns the old `daily_sessions` tally into legacy records so the history
+    /// log becomes the single source of truth.
+    pub fn migrate_daily_counts<'a>(
+        daily_sessions: impl IntoIterator<Item = (&'a String, &'a u64)>,
+    ) -> Result<Vec<SessionRecord>> {
+        let records = Self::records_from_daily_counts(daily_sessions);
+        Self::append(&records)?;
+        Ok(records)
+    }
+
+    /// Appends the records whose id is not in the log yet and returns how many
+    /// were new.
+    pub fn merge(records: Vec<SessionRecord>) -> Result<usize> {
+        let _lock = Config::lock()?;
+        let known: HashSet<String> = Self::load()?.into_iter().map(|r| r.id).collect();
+        let mut seen = HashSet::new();
+        let mut new_records: Vec<SessionRecord> = records
+            .into_iter()
+            .filter(|r| !known.contains(&r.id) && seen.insert(r.id.clone()))
+            .collect();
+        new_records.sort_by_key(|r| r.started_at);
+        Self::append(&new_records)?;
+        Ok(new_records.len())
+    }
+
+    /// The ids only depend on the tally, so turning the same stats.toml into
+    /// records twice gives the same ids and `merge` skips the second import.
+    pub fn records_from_daily_counts<'a>(
+        daily_sessions: impl IntoIterator<Item = (&'a String, &'a u64)>,
+    ) -> Vec<SessionRecord> {
+        let mut daily_sessions: Vec<_> = daily_sessions.into_iter().collect();
+        daily_sessions.sort();
+        let source = Self::tally_hash(&daily_sessions);
+        let mut records = Vec::new();
+
+        for (date, count) in daily_sessions {
+            let Some(started_at) = date
+                .parse::<NaiveDate>()
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .and_then(|d| d.and_local_timezone(Local).earliest())
+            else {
+                continue;
+            };
+
+            for n in 0..*count {
+                records.push(SessionRecord {
+                    id: format!("legacy-{:016x}-{}-{}", source, date, n),
+                    kind: SessionKind::Work,
+                    outcome: SessionOutcome::Completed,
+                    started_at,
+                    ended_at: started_at,
+                    planned_secs: 0,
+                    focused_secs: 0,
+                    pauses: 0,
+                    tag: None,
+                    legacy: true,
+                    suspension: None,
+                    extended_secs: 0,
+                    phase: None,
+                });
+            }
+        }
+
+        records.sort_by_key(|r| r.started_at);
+        records
+    }
+
+    /// FNV-1a over the sorted tally. `DefaultHasher` is not guaranteed to
+    /// give the same result across Rust releases, and these ids are stored.
+    fn tally_hash(daily_sessions: &[(&String, &u64)]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for (date, count) in daily_sessions {
+            for byte in format!("{}={};", date, count).bytes() {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    /// Like `load`, but a damaged log is backed up and rewritten with every
+    /// `[[sessions]]` entry that still parses. Returns a warning when that
+    /// happened. Callers are expected to hold `Config::lock`.
+    pub fn load_or_recover() -> Result<(Vec<SessionRecord>, Option<String>)> {
+        let history_path = Self::get_history_path()?;
+        if !history_path.exists() {
+            return Ok((Vec::new(), None));
+        }
+
+        let history_str = fs::read_to_string(&history_path)?;
+        if let Ok(records) = Self::parse(&history_str) {
+            return Ok((records, None));
+        }
+
+        let backup_path = Config::backup_corrupt(&history_path)?;
+        let records: Vec<SessionRecord> = history_str
+            .split("[[sessions]]")
+            .skip(1)
+            .filter_map(|chunk| Self::parse(&format!("[[sessions]]{}", chunk)).ok())
+            .flatten()
+            .collect();
+
+        let mut recovered = String::new();
+        if !records.is_empty() {
+            recovered = toml::to_string_pretty(&HistoryFile {
+                sessions: records.clone(),
+            })? + "\n";
+        }
+        Config::write_atomic(&history_path, &recovered)?;
+
+        let warning = format!(
+            "history.toml was damaged, recovered {} sessions (original saved to {})",
+            records.len(),
+            backup_path.display()
+        );
+        Ok((records, Some(warning)))
+    }
+
+    pub fn parse(history_str: &str) -> Result<Vec<SessionRecord>> {
+        let history: HistoryFile = toml::from_str(history_str)?;
+        Ok(history.sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ids(daily_sessions: &HashMap<String, u64>) -> Vec<String> {
+        SessionHistory::records_from_daily_counts(daily_sessions)
+            .into_iter()
+            .map(|r| r.id)
+            .collect()
+    }
+
+    #[test]
+    fn legacy_ids_are_stable_for_the_same_tally() {
+        let tally = HashMap::from([("2024-03-01".to_string(), 3), ("2024-03-02".to_string(), 1)]);
+        let first = ids(&tally);
+        assert_eq!(first.len(), 4);
+        assert_eq!(first, ids(&tally.clone()));
+        assert_eq!(first.iter().collect::<HashSet<_>>().len(), 4);
+    }
+
+    #[test]
+    fn legacy_ids_differ_between_tallies() {
+        let ours = HashMap::from([("2024-03-01".to_string(), 2)]);
+        let theirs = HashMap::from([("2024-03-01".to_string(), 3)]);
+        let ours = ids(&ours);
+        assert!(ids(&theirs).iter().all(|id| !ours.contains(id)));
+    }
+}
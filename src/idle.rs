@@ -0,0 +1,48 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Tells how long the user has been away from the machine, for sources of
+/// activity the terminal cannot see.
+pub trait IdleSource: std::fmt::Debug + Send {
+    /// `None` when the source has nothing to go on right now.
+    fn idle_for(&self) -> Option<Duration>;
+}
+
+/// A file holding the desktop idle time in milliseconds, as printed by
+/// `xprintidle` and similar tools, kept up to date by a script or service.
+#[derive(Debug)]
+pub struct IdleFile {
+    path: PathBuf,
+}
+
+impl IdleFile {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+impl IdleSource for IdleFile {
+    fn idle_for(&self) -> Option<Duration> {
+        let contents = fs::read_to_string(&self.path).ok()?;
+        let millis: u64 = contents.split_whitespace().next()?.parse().ok()?;
+        // Whatever wrote the file may have stopped, the idle time keeps
+        // growing since it was written
+        let age = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+        Some(Duration::from_millis(millis) + age)
+    }
+}
+
+/// The source configured in the settings, if any. Terminal input is always
+/// watched on top of it.
+pub fn from_path(path: &Path) -> Option<Box<dyn IdleSource>> {
+    (!path.as_os_str().is_empty()).then(|| Box::new(IdleFile::new(path)) as Box<dyn IdleSource>)
+}
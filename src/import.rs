@@ -0,0 +1,52 @@
+use color_eyre::{Result, eyre::eyre};
+use std::fs;
+
+use crate::cli::ImportArgs;
+use crate::history::SessionHistory;
+use crate::stats::SessionStats;
+
+pub fn import_stats(args: &ImportArgs) -> Result<()> {
+    // Loading stats first migrates our own stats.toml, so the imported counts
+    // are added on top of it rather than replacing it
+    SessionStats::load_stats()?;
+
+    // A newer fokus keeps its history next to stats.toml, prefer that
+    let sibling_history = args
+        .path
+        .file_name()
+        .filter(|name| *name == "stats.toml")
+        .map(|_| args.path.with_file_name("history.toml"))
+        .filter(|path| path.exists());
+    let path = sibling_history.as_ref().unwrap_or(&args.path);
+
+    let contents = fs::read_to_string(path)?;
+    let table: toml::Table = toml::from_str(&contents)?;
+
+    let records = if table.contains_key("sessions") {
+        SessionHistory::parse(&contents)?
+    } else if table.contains_key("daily_sessions") {
+        let other: SessionStats = toml::from_str(&contents)?;
+        SessionHistory::records_from_daily_counts(&other.daily_sessions)
+    } else {
+        return Err(eyre!(
+            "{} is neither a fokus stats.toml nor a history.toml",
+            path.display()
+        ));
+    };
+
+    let found = records.len();
+    let imported = SessionHistory::merge(records)?;
+
+    let stats = SessionStats::load_stats()?;
+    SessionStats::save_stats(&stats)?;
+
+    println!(
+        "Imported {} of {} sessions from {} ({} already present)",
+        imported,
+        found,
+        path.display(),
+        found - imported
+    );
+    println!("Total sessions: {}", stats.get_total_sessions());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use color_eyre::{Result, eyre::eyre};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+};
+use tokio::sync::oneshot;
+#[cfg(unix)]
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt},
+    net::UnixListener,
+    sync::mpsc,
+};
+
+use crate::config::Config;
+
+/// What another process can ask a running fokus to do, sent as one line of
+/// JSON over `fokus.sock` in the config directory.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Add seconds to the running countdown.
+    Extend(u64),
+    /// Take seconds off the running countdown.
+    Shorten(u64),
+}
+
+/// A command on its way to the app, with somewhere to send the answer.
+#[derive(Debug)]
+pub struct RemoteRequest {
+    pub command: RemoteCommand,
+    pub reply: oneshot::Sender<String>,
+}
+
+fn get_socket_path() -> Result<PathBuf> {
+    Ok(Config::get_config_dir()?.join("fokus.sock"))
+}
+
+/// Sends `command` to the running instance and returns its answer.
+#[cfg(unix)]
+pub fn send(command: RemoteCommand) -> Result<String> {
+    let path = get_socket_path()?;
+    let mut stream = UnixStream::connect(&path).map_err(|_| eyre!("fokus is not running"))?;
+    writeln!(stream, "{}", serde_json::to_string(&command)?)?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    Ok(answer.trim_end().to_string())
+}
+
+#[cfg(not(unix))]
+pub fn send(_command: RemoteCommand) -> Result<String> {
+    Err(eyre!(
+        "Remote control needs a Unix domain socket, which this platform lacks"
+    ))
+}
+
+/// Binds the socket, unless another instance already answers on it, in which
+/// case that one keeps taking the commands.
+#[cfg(unix)]
+pub fn bind() -> Result<Option<UnixListener>> {
+    let path = get_socket_path()?;
+    if UnixStream::connect(&path).is_ok() {
+        return Ok(None);
+    }
+    // Left behind by an instance that did not shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    Ok(Some(UnixListener::bind(&path)?))
+}
+
+/// Forwards every command received on `listener` to the app until it goes
+/// away.
+#[cfg(unix)]
+pub async fn serve(listener: UnixListener, requests: mpsc::Sender<RemoteRequest>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let requests = requests.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut line = String::new();
+            if tokio::io::BufReader::new(read)
+                .read_line(&mut line)
+                .await
+                .is_err()
+            {
+                return;
+            }
+
+            let answer = match serde_json::from_str(&line) {
+                Ok(command) => {
+                    let (reply, answer) = oneshot::channel();
+                    if requests
+                        .send(RemoteRequest { command, reply })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                    answer.await.unwrap_or_default()
+                }
+                Err(e) => format!("Invalid command: {}", e),
+            };
+            let _ = write.write_all(format!("{}\n", answer).as_bytes()).await;
+        });
+    }
+}
+
+/// Removes the socket on the way out, so `send` fails fast afterwards.
+pub fn unbind() {
+    if let Ok(path) = get_socket_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
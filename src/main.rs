@@ -2,16 +2,24 @@ use clap::Parser;
 use color_eyre::Result;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use futures::{FutureExt, StreamExt};
-use notify_rust::{Notification, get_bundle_identifier_or_default, set_application};
+use notify_rust::Notification;
+#[cfg(target_os = "macos")]
+use notify_rust::{get_bundle_identifier_or_default, set_application};
 use ratatui::{DefaultTerminal, Frame};
-use tokio::{
-    sync::{broadcast, mpsc},
-    task::JoinHandle,
-};
+use tokio::sync::mpsc;
 
 mod cli;
 mod config;
+mod dialog;
+mod duration;
+mod export;
+mod history;
+mod idle;
+mod import;
+mod ipc;
+mod report;
 mod settings;
+mod state;
 mod stats;
 mod timer;
 mod ui;
@@ -19,32 +27,85 @@ mod ui;
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
-    #[arg(short, long, default_value_t = 25, value_parser = cli::validate_time)]
-    working_time: u64,
-    #[arg(short, long, default_value_t = 5, value_parser = cli::validate_time)]
-    break_time: u64,
-    #[arg(short, long, default_value_t = 15, value_parser = cli::validate_time)]
-    long_break_time: u64,
-    #[arg(short, long, default_value_t = 2, value_parser = cli::validate_time)]
-    sessions_until_break_time: u64,
+    /// Fokus session length, e.g. 25m, 90s or 1h30m; plain numbers are minutes [default: 25m]
+    #[arg(short, long, value_parser = settings::parse_duration)]
+    working_time: Option<duration::Seconds>,
+    /// Short break length [default: 5m]
+    #[arg(short, long, value_parser = settings::parse_duration)]
+    break_time: Option<duration::Seconds>,
+    /// Long break length [default: 15m]
+    #[arg(short, long, value_parser = settings::parse_duration)]
+    long_break_time: Option<duration::Seconds>,
+    /// Fokus sessions before a long break [default: 2]
+    #[arg(short, long, value_parser = settings::parse_sessions_until_long_break)]
+    sessions_until_break_time: Option<u64>,
+    /// Named profile from settings.toml to use
+    #[arg(short, long)]
+    profile: Option<String>,
+    /// Task or tag to attach to fokus sessions, e.g. "code-review"
+    #[arg(short, long, value_parser = cli::validate_tag)]
+    tag: Option<String>,
+    /// Count fokus sessions up until ended with 'e', then take a break in proportion
+    #[arg(short, long)]
+    flowtime: bool,
+    #[command(subcommand)]
+    command: Option<cli::Command>,
 }
 
 impl Cli {
-    pub fn get_working_time(&self) -> u64 {
-        self.working_time * 60
-    }
-
-    pub fn get_break_time(&self) -> u64 {
-        self.break_time * 60
+    /// Only the flags that were actually passed, so they override everything
+    /// else without masking `settings.toml` when absent.
+    pub fn settings_layer(&self) -> settings::SettingsLayer {
+        settings::SettingsLayer {
+            working_time: self.working_time,
+            break_time: self.break_time,
+            long_break_time: self.long_break_time,
+            sessions_until_long_break: self.sessions_until_break_time,
+            flowtime: self.flowtime.then_some(true),
+            ..Default::default()
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum TimerState {
     Work,
     Break,
 }
 
+/// How much the `+` and `-` keys add to or take off the running countdown.
+const EXTEND_STEP: u64 = 5 * 60;
+
+/// A flowtime session counts down from here and shows the time spent, which
+/// leaves pausing, sleep handling and resuming to the usual countdown.
+const FLOWTIME_LIMIT: u64 = settings::MAX_DURATION.0;
+
+/// How often the idle time is looked at while a countdown is running.
+const IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How long a notice like "the timer was paused" stays on the timer screen.
+const STATUS_MESSAGE_SECS: u64 = 10;
+
+/// Actions that throw away a countdown that is still on the clock.
+#[derive(Debug, Clone, Copy)]
+pub enum StopAction {
+    Quit,
+    Reset,
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DialogAction {
+    /// Stop without counting the time spent.
+    Abandon(StopAction),
+    /// Stop and keep the time spent as a skipped session.
+    SavePartial(StopAction),
+    Resume,
+    StartOver,
+    Cancel,
+}
+
 #[derive(Debug)]
 pub struct App {
     app_running: bool,
@@ -53,239 +114,949 @@ pub struct App {
     remaining_timer: u64,
     countdown_running: bool,
     timer_active: bool,
-    transmitter: mpsc::Sender<u64>,
-    running_tx: broadcast::Sender<bool>,
-    countdown_task: Option<JoinHandle<()>>,
-    transition_pending: bool,
+    /// The countdown on the clock is a flowtime session.
+    counting_up: bool,
+    /// Break length earned by the last flowtime session.
+    flow_break: Option<u64>,
+    timer_tx: mpsc::Sender<timer::TimerCommand>,
+    remote_rx: mpsc::Receiver<ipc::RemoteRequest>,
+    owns_socket: bool,
     current_screen: settings::Screen,
     settings: settings::Settings,
+    settings_file: settings::SettingsFile,
+    overrides: settings::SettingsLayer,
     settings_field: settings::SettingsField,
     editing_field: bool,
     input_buffer: String,
+    input_error: Option<String>,
     long_break_count: u64,
-    settings_saved_message: Option<std::time::Instant>,
+    /// Position in the user-defined `cycle`, if there is one.
+    phase_index: usize,
+    active_session: Option<history::ActiveSession>,
+    pending_resume: Option<state::CountdownSnapshot>,
+    /// When the next phase starts by itself, if auto-start is on for it.
+    auto_start_at: Option<std::time::Instant>,
+    /// Work sessions followed by their break in this run, for
+    /// `stop_after_cycles`.
+    cycles_completed: u64,
+    /// The last key press, for `idle_timeout`.
+    last_input: std::time::Instant,
+    idle_source: Option<Box<dyn idle::IdleSource>>,
+    /// The countdown was paused because nobody was around.
+    away: bool,
+    dialog: Option<dialog::Dialog<DialogAction>>,
+    current_tag: Option<String>,
+    settings_saved_message: Option<(std::time::Instant, String)>,
     stats: stats::SessionStats,
     stats_saved_message: Option<std::time::Instant>,
+    stats_range: stats::StatsRange,
+    /// Routine notices, shown for a while; `warnings` is for failures.
+    status_message: Option<(std::time::Instant, String)>,
+    warnings: Vec<String>,
     pub show_help: bool,
 }
 
 impl App {
-    pub fn new(args: Cli) -> (Self, mpsc::Receiver<u64>) {
-        let (tx, rx) = mpsc::channel(100);
-        let (running_tx, _) = broadcast::channel(100);
-
-        let settings = config::Config::load_settings().unwrap_or_else(|_| settings::Settings {
-            working_time: args.working_time,
-            break_time: args.break_time,
-            long_break_time: args.long_break_time,
-            sessions_until_long_break: args.sessions_until_break_time,
+    pub fn new(args: Cli) -> (Self, mpsc::Receiver<timer::TimerEvent>) {
+        let (timer_tx, timer_rx) = mpsc::channel(100);
+        let (event_tx, rx) = mpsc::channel(100);
+        tokio::spawn(timer::run(timer_rx, event_tx));
+
+        let mut warnings = Vec::new();
+
+        // `fokus extend` and friends talk to the first instance started
+        let (remote_tx, remote_rx) = mpsc::channel(10);
+        #[allow(unused_mut)]
+        let mut owns_socket = false;
+        #[cfg(unix)]
+        match ipc::bind() {
+            Ok(Some(listener)) => {
+                owns_socket = true;
+                tokio::spawn(ipc::serve(listener, remote_tx));
+            }
+            Ok(None) => {}
+            Err(e) => warnings.push(format!("Remote control is unavailable: {}", e)),
+        }
+        #[cfg(not(unix))]
+        drop(remote_tx);
+
+        let mut settings_file = match config::Config::load_settings_with_warnings() {
+            Ok((settings_file, settings_warnings)) => {
+                warnings.extend(settings_warnings);
+                settings_file
+            }
+            Err(e) => {
+                warnings.push(e.to_string());
+                settings::SettingsFile::default()
+            }
+        };
+        if let Some(profile) = &args.profile
+            && let Err(e) = settings_file.set_cli_profile(profile.clone())
+        {
+            warnings.push(e);
+        }
+        let mut overrides = match config::Config::load_env_layer_with_warnings() {
+            Ok((overrides, env_warnings)) => {
+                warnings.extend(env_warnings);
+                overrides
+            }
+            Err(e) => {
+                warnings.push(e.to_string());
+                settings::SettingsLayer::default()
+            }
+        };
+        overrides.merge(&args.settings_layer());
+
+        let mut settings = settings_file.resolve();
+        overrides.apply_to(&mut settings);
+        let idle_source = idle::from_path(&settings.idle_file);
+
+        let stats = match stats::SessionStats::load_stats_with_warnings() {
+            Ok((stats, stats_warnings)) => {
+                warnings.extend(stats_warnings);
+                stats
+            }
+            Err(e) => {
+                warnings.push(format!("Could not load stats: {}", e));
+                stats::SessionStats::default()
+            }
+        };
+
+        // The cycle position always carries over, a countdown that was still
+        // on the clock is offered on the timer screen first
+        let snapshot = state::TimerSnapshot::load().unwrap_or_else(|e| {
+            warnings.push(e.to_string());
+            None
         });
+        let (current_state, long_break_count, phase_index, flow_break, pending_resume) =
+            match snapshot {
+                Some(snapshot) => (
+                    snapshot.current_state,
+                    snapshot.long_break_count,
+                    snapshot.phase_index,
+                    snapshot.flow_break_secs,
+                    snapshot.countdown,
+                ),
+                None => (TimerState::Work, 0, 0, None, None),
+            };
 
-        let stats = stats::SessionStats::load_stats().unwrap_or_default();
-
-        (
-            Self {
-                app_running: true,
-                event_stream: EventStream::new(),
-                current_state: TimerState::Work,
-                remaining_timer: 0,
-                countdown_running: false,
-                timer_active: false,
-                transmitter: tx,
-                running_tx,
-                countdown_task: None,
-                transition_pending: false,
-                current_screen: settings::Screen::Timer,
-                settings,
-                settings_field: settings::SettingsField::WorkingTime,
-                editing_field: false,
-                input_buffer: String::new(),
-                long_break_count: 0,
-                settings_saved_message: None,
-                stats,
-                stats_saved_message: None,
-                show_help: false,
-            },
-            rx,
-        )
+        let mut app = Self {
+            app_running: true,
+            event_stream: EventStream::new(),
+            current_state,
+            remaining_timer: 0,
+            countdown_running: false,
+            timer_active: false,
+            counting_up: false,
+            flow_break,
+            timer_tx,
+            remote_rx,
+            owns_socket,
+            current_screen: settings::Screen::Timer,
+            settings,
+            settings_file,
+            overrides,
+            settings_field: settings::SettingsField::Profile,
+            editing_field: false,
+            input_buffer: String::new(),
+            input_error: None,
+            long_break_count,
+            phase_index,
+            active_session: None,
+            auto_start_at: None,
+            cycles_completed: 0,
+            last_input: std::time::Instant::now(),
+            idle_source,
+            away: false,
+            dialog: pending_resume.as_ref().map(resume_dialog),
+            pending_resume,
+            current_tag: args.tag,
+            settings_saved_message: None,
+            stats,
+            stats_saved_message: None,
+            stats_range: stats::StatsRange::Week,
+            status_message: None,
+            warnings,
+            show_help: false,
+        };
+        // The cycle may have been edited since the state was saved
+        app.sync_cycle_state();
+        (app, rx)
     }
 
     pub async fn run(
         mut self,
         mut terminal: DefaultTerminal,
-        mut rx: mpsc::Receiver<u64>,
+        mut rx: mpsc::Receiver<timer::TimerEvent>,
     ) -> Result<()> {
         self.app_running = true;
+        // Keeps its schedule across loop iterations, unlike a fresh sleep
+        // that every timer tick would cut short
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        idle_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
         while self.app_running {
             terminal.draw(|frame| self.draw(frame))?;
             tokio::select! {
                 event = self.event_stream.next().fuse() => {
-                    match event {
-                        Some(Ok(evt)) => {
-                            match evt {
-                                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if let Some(Ok(evt)) = event {
+                        match evt {
+                            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                                // The key that brings the user back does
+                                // nothing else
+                                let was_away = self.away;
+                                self.on_input();
+                                if !was_away {
                                     self.on_key_event(key);
                                 }
-                                Event::Mouse(_) => {}
-                                Event::Resize(_, _) => {}
-                                _ => {}
                             }
+                            Event::Mouse(_) => {}
+                            Event::Resize(_, _) => {}
+                            _ => {}
                         }
-                        _ => {}
                     }
                 }
-                Some(secs) = rx.recv() => {
-                    self.remaining_timer = secs;
-                    if secs == 0 && !self.transition_pending {
-                        self.transition_pending = true;
-                        self.countdown_running = false;
-                        self.timer_active = false;
-
-                        let (summary, _body) = match self.current_state {
-                            TimerState::Work => {
-                                self.long_break_count += 1;
-
-                                self.stats.increment_session();
-                                self.save_stats();
-
-                                if self.long_break_count % self.settings.sessions_until_long_break == 0 {
-                                    ("Session Finished", "Time for a long break!")
-                                } else {
-                                    ("Session Finished", "Time for a short break")
-                                }
-                            },
-                            TimerState::Break => {
-                                if self.long_break_count > 0 && self.long_break_count % self.settings.sessions_until_long_break == 0 {
-                                    self.long_break_count = 0;
-                                }
-                                ("Break Finished", "Time for another session")
-                            }
-                        };
+                Some(event) = rx.recv() => {
+                    self.on_timer_event(event)?;
+                }
+                Some(request) = self.remote_rx.recv() => {
+                    let answer = match request.command {
+                        ipc::RemoteCommand::Extend(secs) => self.adjust_timer(secs as i64),
+                        ipc::RemoteCommand::Shorten(secs) => self.adjust_timer(-(secs as i64)),
+                    };
+                    let _ = request.reply.send(answer);
+                }
+                _ = tokio::time::sleep(std::time::Duration::from_millis(250)),
+                    if self.auto_start_at.is_some() =>
+                {
+                    // Also redraws the countdown until the start
+                    if self
+                        .auto_start_at
+                        .is_some_and(|at| at <= std::time::Instant::now())
+                    {
+                        self.start_timer();
+                    }
+                }
+                _ = idle_check.tick(), if self.watching_idle() => {
+                    self.check_idle();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_timer_event(&mut self, event: timer::TimerEvent) -> Result<()> {
+        // Events still queued from a countdown that was reset or skipped
+        if !self.timer_active {
+            return Ok(());
+        }
+
+        match event {
+            timer::TimerEvent::Tick { remaining, elapsed } => {
+                if let Some(session) = self.active_session.as_mut() {
+                    session.add_elapsed(elapsed);
+                }
+                self.remaining_timer = remaining;
+                // Flowtime is saved as paused, so keep the time spent fresh
+                if self.counting_up && remaining.is_multiple_of(60) {
+                    self.save_state();
+                }
+            }
+            timer::TimerEvent::Paused { remaining } => {
+                self.remaining_timer = remaining;
+                self.countdown_running = false;
+            }
+            timer::TimerEvent::Resumed { remaining } => {
+                self.remaining_timer = remaining;
+                self.countdown_running = true;
+            }
+            timer::TimerEvent::Extended { remaining } => self.remaining_timer = remaining,
+            timer::TimerEvent::Finished => self.finish_phase()?,
+            timer::TimerEvent::Suspended { secs, remaining } => {
+                self.remaining_timer = remaining;
+                self.on_suspend(secs)?;
+            }
+        }
+        if !matches!(event, timer::TimerEvent::Tick { .. }) {
+            self.save_state();
+        }
+        Ok(())
+    }
+
+    /// Writes the cycle position and any countdown on the clock to
+    /// `state.toml`.
+    fn save_state(&mut self) {
+        // Keep the old countdown around until the user decided about it
+        if self.pending_resume.is_some() {
+            return;
+        }
 
+        // Time spent with fokus closed is not focus, so a flowtime session
+        // is kept as paused rather than left running toward its deadline
+        let running = self.countdown_running && !self.counting_up;
+        let countdown = match &self.active_session {
+            Some(session) if self.timer_active => Some(state::CountdownSnapshot {
+                paused: !running,
+                remaining_secs: self.remaining_timer,
+                deadline: running.then(|| {
+                    chrono::Local::now() + chrono::Duration::seconds(self.remaining_timer as i64)
+                }),
+                session: session.clone(),
+                counting_up: self.counting_up,
+            }),
+            _ => None,
+        };
+        let snapshot = state::TimerSnapshot {
+            current_state: self.current_state,
+            long_break_count: self.long_break_count,
+            phase_index: self.phase_index,
+            flow_break_secs: self.flow_break,
+            countdown,
+        };
+        if let Err(e) = snapshot.save() {
+            self.warnings
+                .push(format!("Failed to save timer state: {}", e));
+        }
+    }
 
+    /// Picks the countdown from the last run back up. One that ran out while
+    /// fokus was closed finishes straight away.
+    fn resume_from_snapshot(&mut self) {
+        let Some(countdown) = self.pending_resume.take() else {
+            return;
+        };
+        let remaining = countdown.remaining_now();
+        let mut session = countdown.session;
+        session.add_elapsed(countdown.remaining_secs.saturating_sub(remaining));
 
-                        Notification::new()
-                            .summary("Pomodoro")
-                            .body(summary)
-                            // .message(body)
-                            .sound_name("Blow")
-                            .icon("alarm")
-                            // .main_button(MainButton::SingleAction("Start Next Session"))
-                            .show()?;
+        self.active_session = Some(session);
+        self.remaining_timer = remaining;
+        self.timer_active = true;
+        let paused = countdown.paused || countdown.counting_up;
+        self.countdown_running = !paused;
+        self.counting_up = countdown.counting_up;
+        self.send_command(timer::TimerCommand::Start(remaining));
+        if paused {
+            self.send_command(timer::TimerCommand::Pause);
+        }
+        self.save_state();
+    }
 
-                        // if let Ok(response) = response {
-                        //     notification_actions::handle_response(response);
-                        // } else {
-                        //    eprint!("Failed to send notification");
-                        // };
+    fn discard_snapshot(&mut self) {
+        self.pending_resume = None;
+        self.save_state();
+    }
 
-                        self.current_state = match self.current_state {
-                            TimerState::Work => TimerState::Break,
-                            TimerState::Break => TimerState::Work,
-                        };
-                    }
+    pub fn get_dialog(&self) -> Option<&dialog::Dialog<DialogAction>> {
+        self.dialog.as_ref()
+    }
+
+    fn handle_dialog_input(&mut self, key: KeyEvent) {
+        let Some(action) = self.dialog.as_ref().and_then(|d| d.answer(key.code)) else {
+            return;
+        };
+        self.dialog = None;
+
+        match action {
+            DialogAction::Abandon(stop) => self.stop(stop, history::SessionOutcome::Discarded),
+            DialogAction::SavePartial(stop) => self.stop(stop, history::SessionOutcome::Skipped),
+            DialogAction::Resume => self.resume_from_snapshot(),
+            DialogAction::StartOver => self.discard_snapshot(),
+            DialogAction::Cancel => {}
+        }
+    }
+
+    /// Asks first when a countdown is on the clock, so a stray key press
+    /// cannot throw it away.
+    fn request_stop(&mut self, action: StopAction) {
+        if !self.timer_active {
+            self.stop(action, history::SessionOutcome::Skipped);
+            return;
+        }
+
+        let (title, question) = match action {
+            StopAction::Quit => ("Quit?", "Quit while the timer is running?"),
+            StopAction::Reset => ("Reset?", "Reset the running timer?"),
+            StopAction::Skip => ("Skip?", "Skip to the next phase?"),
+        };
+        let message = format!(
+            "{}\n{}",
+            question,
+            format_focused(self.active_session.as_ref())
+        );
+        self.dialog = Some(
+            dialog::Dialog::new(title, message)
+                .choice('a', "abandon", DialogAction::Abandon(action))
+                .choice('s', "save partial time", DialogAction::SavePartial(action))
+                .choice('c', "cancel", DialogAction::Cancel)
+                .on_esc('c'),
+        );
+    }
+
+    /// Ends the countdown, recording it with `outcome` if one was running.
+    fn stop(&mut self, action: StopAction, outcome: history::SessionOutcome) {
+        self.auto_start_at = None;
+        self.away = false;
+        match action {
+            StopAction::Skip => self.skip_session(outcome),
+            StopAction::Reset | StopAction::Quit => {
+                if self.timer_active {
+                    self.send_command(timer::TimerCommand::Reset);
+                    self.record_session(outcome);
+                }
+                self.remaining_timer = 0;
+                self.countdown_running = false;
+                self.timer_active = false;
+                self.active_session = None;
+                self.save_state();
+                if matches!(action, StopAction::Quit) {
+                    self.quit();
+                }
+            }
+        }
+    }
+
+    /// The timer has already paused itself, apply the configured policy.
+    fn on_suspend(&mut self, secs: u64) -> Result<()> {
+        let policy = self.settings.suspend_policy;
+        let slept = duration::Seconds(secs);
+        if let Some(session) = self.active_session.as_mut() {
+            session.add_suspension(secs, policy);
+        }
+
+        match policy {
+            settings::SuspendPolicy::Pause => {
+                self.countdown_running = false;
+                if let Some(session) = self.active_session.as_mut() {
+                    session.add_pause();
                 }
+                self.show_status(format!(
+                    "The computer slept for {}, the timer was paused",
+                    slept
+                ));
+            }
+            settings::SuspendPolicy::Count => {
+                if let Some(session) = self.active_session.as_mut() {
+                    session.add_elapsed(secs.min(self.remaining_timer));
+                }
+                self.show_status(format!(
+                    "The computer slept for {}, counted as elapsed",
+                    slept
+                ));
+                if secs >= self.remaining_timer {
+                    self.send_command(timer::TimerCommand::Reset);
+                    self.finish_phase()?;
+                } else {
+                    self.send_command(timer::TimerCommand::Subtract(secs));
+                    self.send_command(timer::TimerCommand::Resume);
+                }
+            }
+            settings::SuspendPolicy::Discard => {
+                self.send_command(timer::TimerCommand::Reset);
+                self.remaining_timer = 0;
+                self.countdown_running = false;
+                self.timer_active = false;
+                self.record_session(history::SessionOutcome::Discarded);
+                self.show_status(format!(
+                    "The computer slept for {}, the session was discarded",
+                    slept
+                ));
             }
         }
         Ok(())
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
-        ui::draw(self, frame);
+    fn watching_idle(&self) -> bool {
+        self.settings.idle_timeout.as_secs() > 0
+            && self.timer_active
+            && (self.countdown_running || self.away)
     }
 
-    fn start_timer(&mut self) {
-        if !self.timer_active {
-            if let Some(task) = self.countdown_task.take() {
-                task.abort();
+    /// Time since the last input in the terminal, or anywhere on the desktop
+    /// when an idle source says so.
+    fn idle_for(&self) -> std::time::Duration {
+        let terminal = self.last_input.elapsed();
+        self.idle_source
+            .as_ref()
+            .and_then(|source| source.idle_for())
+            .map_or(terminal, |desktop| desktop.min(terminal))
+    }
+
+    /// Pauses the countdown once the user has been idle for `idle_timeout`,
+    /// and notices when an idle source reports them back.
+    fn check_idle(&mut self) {
+        let timeout = std::time::Duration::from_secs(self.settings.idle_timeout.as_secs());
+        let idle = self.idle_for();
+        if self.away {
+            if idle < timeout {
+                self.on_return();
+            }
+            return;
+        }
+        if !self.countdown_running || idle < timeout {
+            return;
+        }
+
+        self.away = true;
+        self.countdown_running = false;
+        if let Some(session) = self.active_session.as_mut() {
+            session.add_pause();
+        }
+        self.send_command(timer::TimerCommand::Pause);
+        self.show_status(format!(
+            "No activity for {}, the timer was paused",
+            self.settings.idle_timeout
+        ));
+    }
+
+    fn on_input(&mut self) {
+        self.last_input = std::time::Instant::now();
+        if self.away {
+            self.on_return();
+        }
+    }
+
+    fn on_return(&mut self) {
+        self.away = false;
+        match self.settings.idle_return {
+            settings::IdleReturn::Resume => {
+                self.resume_timer();
+                self.save_state();
+            }
+            settings::IdleReturn::Discard => {
+                self.stop(StopAction::Reset, history::SessionOutcome::Discarded);
+                self.show_status("You were away, the session was discarded".to_string());
             }
+        }
+    }
 
-            self.transition_pending = false;
+    fn finish_phase(&mut self) -> Result<()> {
+        // A question about the countdown that just ended no longer applies
+        self.dialog = None;
+        self.away = false;
+        self.remaining_timer = 0;
+        self.countdown_running = false;
+        self.timer_active = false;
 
-            let duration = match self.current_state {
-                TimerState::Work => self.settings.get_working_time_seconds(),
-                TimerState::Break => {
-                    if self.long_break_count > 0
-                        && self.long_break_count % self.settings.sessions_until_long_break == 0
+        if self.counting_up {
+            let hit_limit = self
+                .active_session
+                .as_ref()
+                .is_some_and(|session| session.focused_secs() >= FLOWTIME_LIMIT);
+            if hit_limit {
+                self.show_status(format!(
+                    "The flowtime session reached the {} limit and was ended",
+                    duration::Seconds(FLOWTIME_LIMIT)
+                ));
+            }
+        }
+        self.end_phase_flow();
+        self.record_session(history::SessionOutcome::Completed);
+
+        let summary = if let Some(phase) = self.settings.cycle_phase(self.phase_index) {
+            let summary = format!("{} Finished", phase.name);
+            self.advance_cycle();
+            summary
+        } else {
+            let (summary, _body) = match self.current_state {
+                TimerState::Work => {
+                    self.long_break_count += 1;
+
+                    if self
+                        .long_break_count
+                        .is_multiple_of(self.settings.sessions_until_long_break)
                     {
-                        self.settings.get_long_break_time_seconds()
+                        ("Session Finished", "Time for a long break!")
                     } else {
-                        self.settings.get_break_time_seconds()
+                        ("Session Finished", "Time for a short break")
+                    }
+                }
+                TimerState::Break => {
+                    if self.is_long_break() {
+                        self.long_break_count = 0;
                     }
+                    self.cycles_completed += 1;
+                    ("Break Finished", "Time for another session")
                 }
             };
+            self.current_state = match self.current_state {
+                TimerState::Work => TimerState::Break,
+                TimerState::Break => TimerState::Work,
+            };
+            summary.to_string()
+        };
+        // The session is in the history now, so the countdown must not be
+        // offered for resuming even if the notification below fails
+        self.save_state();
+
+        Notification::new()
+            .summary("Pomodoro")
+            .body(&summary)
+            // .message(body)
+            .sound_name("Blow")
+            .icon("alarm")
+            // .main_button(MainButton::SingleAction("Start Next Session"))
+            .show()?;
+
+        // if let Ok(response) = response {
+        //     notification_actions::handle_response(response);
+        // } else {
+        //    eprint!("Failed to send notification");
+        // };
+
+        self.schedule_auto_start();
+        Ok(())
+    }
+
+    /// Lines up the next phase to start by itself when auto-start is on for
+    /// it, unless the run just reached `stop_after_cycles`.
+    fn schedule_auto_start(&mut self) {
+        let cycles = self.settings.stop_after_cycles;
+        if cycles > 0 && self.cycles_completed >= cycles {
+            self.cycles_completed = 0;
+            self.auto_start_at = None;
+            self.show_status(format!("All {} cycles done, the run is over", cycles));
+            let _ = Notification::new()
+                .summary("Pomodoro")
+                .body(&format!("All {} cycles done", cycles))
+                .sound_name("Glass")
+                .icon("alarm")
+                .show();
+            return;
+        }
 
+        let enabled = match self.current_state {
+            TimerState::Work => self.settings.auto_start_work,
+            TimerState::Break => self.settings.auto_start_breaks,
+        };
+        self.auto_start_at = enabled.then(|| {
+            std::time::Instant::now()
+                + std::time::Duration::from_secs(self.settings.auto_start_delay.as_secs())
+        });
+    }
+
+    fn cancel_auto_start(&mut self) {
+        self.auto_start_at = None;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        ui::draw(self, frame);
+    }
+
+    fn start_timer(&mut self) {
+        self.auto_start_at = None;
+        if !self.timer_active {
+            self.counting_up =
+                self.settings.flowtime && matches!(self.current_state, TimerState::Work);
+            let duration = if self.counting_up {
+                FLOWTIME_LIMIT
+            } else {
+                self.current_phase_duration()
+            };
+            if matches!(self.current_state, TimerState::Work) {
+                self.flow_break = None;
+            }
+
+            // A flowtime session plans what it ends up focusing
+            let mut session = history::ActiveSession::new(
+                self.current_session_kind(),
+                if self.counting_up { 0 } else { duration },
+                self.tag_for_current_state(),
+            );
+            session.set_phase(
+                self.settings
+                    .cycle_phase(self.phase_index)
+                    .map(|phase| phase.name.clone()),
+            );
+            self.active_session = Some(session);
             self.remaining_timer = duration;
             self.countdown_running = true;
             self.timer_active = true;
+            self.send_command(timer::TimerCommand::Start(duration));
+            self.save_state();
+        } else {
+            self.resume_timer();
+        }
+    }
+
+    fn current_phase_duration(&self) -> u64 {
+        self.current_phase().duration.as_secs()
+    }
+
+    /// Called as a phase ends. A flowtime session planned what it focused,
+    /// and the break after it gets its share of that. Any other phase ends
+    /// whatever break an earlier flowtime session earned.
+    fn end_phase_flow(&mut self) {
+        if !self.counting_up {
+            self.flow_break = None;
+            return;
+        }
+        self.counting_up = false;
+        if let Some(session) = self.active_session.as_mut() {
+            let focused = session.focused_secs();
+            session.set_planned(focused);
+            let secs = (focused as f64 * self.settings.flowtime_break_ratio).round() as u64;
+            // A zero-length break would finish the moment it started
+            self.flow_break = Some(secs.max(1));
+        }
+    }
+
+    /// Ends the flowtime session on the clock as completed, by running its
+    /// countdown out.
+    fn end_flow(&mut self) {
+        if self.timer_active && self.counting_up {
+            self.send_command(timer::TimerCommand::Subtract(FLOWTIME_LIMIT));
+        }
+    }
+
+    /// Called after the settings changed underneath a running countdown.
+    /// Returns a note for the user when the countdown was left alone.
+    fn retarget_timer(&mut self, old_duration: u64) -> Option<String> {
+        let new_duration = self.current_phase_duration();
+        if !self.timer_active || self.counting_up || new_duration == old_duration {
+            return None;
+        }
+        if self.settings.live_apply == settings::LiveApply::Off {
+            return Some("the running timer keeps its length until the next session".to_string());
+        }
 
-            let tx = self.transmitter.clone();
-            let running_rx = self.running_tx.subscribe();
+        // Time added or taken off with `+`/`-` stays added or taken off
+        let (old_length, new_length) = match &self.active_session {
+            Some(session) => (
+                session.length_secs(),
+                new_duration.saturating_add_signed(session.extended_secs()),
+            ),
+            None => (old_duration, new_duration),
+        };
+        let remaining =
+            self.settings
+                .live_apply
+                .retarget(self.remaining_timer, old_length, new_length);
+        if let Some(session) = self.active_session.as_mut() {
+            session.set_planned(new_duration);
+        }
+        self.send_command(if remaining >= self.remaining_timer {
+            timer::TimerCommand::AddTime(remaining - self.remaining_timer)
+        } else {
+            timer::TimerCommand::Subtract(self.remaining_timer - remaining)
+        });
+        Some(format!(
+            "{} left on the running timer",
+            duration::Seconds(remaining)
+        ))
+    }
+
+    /// Adds (or with a negative `delta` takes off) time on the running
+    /// countdown, and returns what happened for `fokus extend` to print.
+    fn adjust_timer(&mut self, delta: i64) -> String {
+        if !self.timer_active {
+            return "No timer is running".to_string();
+        }
+        if self.counting_up {
+            return "The timer counts up, there is nothing to extend".to_string();
+        }
 
-            let _ = self.running_tx.send(true);
+        let secs = delta.unsigned_abs();
+        if delta >= 0 {
+            self.send_command(timer::TimerCommand::AddTime(secs));
+        } else {
+            self.send_command(timer::TimerCommand::Subtract(secs));
+        }
+        // Taking off more than is left only ends the phase early
+        let delta = delta.max(-(self.remaining_timer as i64));
+        if let Some(session) = self.active_session.as_mut() {
+            session.add_extension(delta);
+        }
 
-            self.countdown_task = Some(tokio::spawn(async move {
-                timer::countdown(duration, tx, running_rx).await;
-            }));
+        let remaining = self.remaining_timer.saturating_add_signed(delta);
+        let change = if delta >= 0 { "Extended" } else { "Shortened" };
+        if remaining == 0 {
+            format!(
+                "{} by {}, the phase is over",
+                change,
+                duration::Seconds(secs)
+            )
         } else {
-            self.resume_timer();
+            format!(
+                "{} by {}, {} left",
+                change,
+                duration::Seconds(secs),
+                duration::Seconds(remaining)
+            )
         }
     }
 
+    fn send_command(&self, command: timer::TimerCommand) {
+        let _ = self.timer_tx.try_send(command);
+    }
+
     fn resume_timer(&mut self) {
         if !self.countdown_running {
             self.countdown_running = true;
-            let _ = self.running_tx.send(true);
+            self.send_command(timer::TimerCommand::Resume);
         }
     }
 
     fn pause_timer(&mut self) {
         if self.timer_active {
             self.countdown_running = !self.countdown_running;
-            let _ = self.running_tx.send(self.countdown_running);
+            if !self.countdown_running
+                && let Some(session) = self.active_session.as_mut()
+            {
+                session.add_pause();
+            }
+            self.send_command(if self.countdown_running {
+                timer::TimerCommand::Resume
+            } else {
+                timer::TimerCommand::Pause
+            });
         }
     }
 
-    fn reset_timer(&mut self) {
-        if !self.countdown_running {
-            if let Some(task) = self.countdown_task.take() {
-                task.abort();
-            }
+    fn skip_session(&mut self, outcome: history::SessionOutcome) {
+        self.send_command(timer::TimerCommand::Skip);
+        self.remaining_timer = 0;
+        self.countdown_running = false;
+        self.timer_active = false;
 
-            self.remaining_timer = 0;
-            self.countdown_running = false;
-            self.timer_active = false;
-            self.countdown_task = None;
-            let _ = self.running_tx.send(false);
+        self.end_phase_flow();
+        self.record_session(outcome);
+
+        if !self.settings.cycle.is_empty() {
+            self.advance_cycle();
         } else {
-            // hanlde confirmation of reset when timer running
-            // so as to ignore accidental presses
+            if matches!(self.current_state, TimerState::Work) && self.settings.skips_advance_cycle {
+                self.long_break_count += 1;
+            }
+            // A break ends the cycle whether it ran out or was skipped, as a
+            // custom cycle's last phase does
+            if matches!(self.current_state, TimerState::Break) {
+                self.cycles_completed += 1;
+            }
+
+            self.current_state = match self.current_state {
+                TimerState::Work => TimerState::Break,
+                TimerState::Break => TimerState::Work,
+            };
         }
+        self.save_state();
+        self.schedule_auto_start();
     }
 
-    fn skip_session(&mut self) {
-        if self.countdown_running {
-            if let Some(task) = self.countdown_task.take() {
-                task.abort();
-            }
-            self.countdown_task = None;
-            self.running_tx.send(false).unwrap();
+    /// Moves a user-defined cycle on to its next phase, counting a cycle each
+    /// time it wraps around.
+    fn advance_cycle(&mut self) {
+        self.phase_index = (self.phase_index + 1) % self.settings.cycle.len().max(1);
+        if self.phase_index == 0 {
+            self.cycles_completed += 1;
         }
-        self.remaining_timer = 0;
-        self.countdown_running = false;
-        self.timer_active = false;
+        self.sync_cycle_state();
+    }
 
-        if matches!(self.current_state, TimerState::Work) {
-            self.long_break_count += 1;
-            self.stats.increment_session();
-            self.save_stats();
+    /// Keeps `current_state` in line with the phase of a user-defined cycle,
+    /// so tags and auto-start treat focus phases like fokus sessions.
+    fn sync_cycle_state(&mut self) {
+        if let Some(phase) = self.settings.cycle_phase(self.phase_index) {
+            self.current_state = if phase.focus {
+                TimerState::Work
+            } else {
+                TimerState::Break
+            };
         }
+    }
 
-        self.current_state = match self.current_state {
-            TimerState::Work => TimerState::Break,
-            TimerState::Break => TimerState::Work,
+    /// The phase on the clock, from the `cycle` in the settings or else the
+    /// built-in work and break phases.
+    pub fn current_phase(&self) -> settings::Phase {
+        let mut phase = match self.settings.cycle_phase(self.phase_index) {
+            Some(phase) => phase.clone(),
+            None => {
+                let (name, duration) = match self.current_session_kind() {
+                    history::SessionKind::Work => ("Fokus Session", self.settings.working_time),
+                    history::SessionKind::ShortBreak => ("Short Break", self.settings.break_time),
+                    history::SessionKind::LongBreak => {
+                        ("Long Break", self.settings.long_break_time)
+                    }
+                };
+                settings::Phase {
+                    name: name.to_string(),
+                    duration,
+                    color: None,
+                    focus: matches!(self.current_state, TimerState::Work),
+                }
+            }
         };
+        if !phase.focus
+            && let Some(secs) = self.flow_break
+        {
+            phase.duration = duration::Seconds(secs);
+        }
+        phase
+    }
+
+    fn is_long_break(&self) -> bool {
+        self.long_break_count > 0
+            && self
+                .long_break_count
+                .is_multiple_of(self.settings.sessions_until_long_break)
+    }
+
+    fn current_session_kind(&self) -> history::SessionKind {
+        if let Some(phase) = self.settings.cycle_phase(self.phase_index) {
+            return if phase.focus {
+                history::SessionKind::Work
+            } else {
+                history::SessionKind::ShortBreak
+            };
+        }
+        match self.current_state {
+            TimerState::Work => history::SessionKind::Work,
+            TimerState::Break if self.is_long_break() => history::SessionKind::LongBreak,
+            TimerState::Break => history::SessionKind::ShortBreak,
+        }
+    }
+
+    fn tag_for_current_state(&self) -> Option<String> {
+        match self.current_state {
+            TimerState::Work => self.current_tag.clone(),
+            TimerState::Break => None,
+        }
+    }
+
+    fn record_session(&mut self, outcome: history::SessionOutcome) {
+        let session = self.active_session.take().unwrap_or_else(|| {
+            // Skipping a phase that was never started still leaves a trace
+            history::ActiveSession::new(
+                self.current_session_kind(),
+                0,
+                self.tag_for_current_state(),
+            )
+        });
+
+        let goal_was_met = self.daily_goal_met();
+        match self.stats.record_session(session.finish(outcome)) {
+            Ok(_) => self.save_stats(),
+            Err(e) => {
+                self.warnings
+                    .push(format!("Failed to record session: {}", e));
+            }
+        }
+
+        if !goal_was_met && self.daily_goal_met() {
+            let _ = Notification::new()
+                .summary("Pomodoro")
+                .body(&format!(
+                    "Daily goal reached: {} sessions",
+                    self.settings.daily_goal
+                ))
+                .sound_name("Glass")
+                .icon("alarm")
+                .show();
+        }
+    }
+
+    fn daily_goal_met(&self) -> bool {
+        self.settings.daily_goal > 0 && self.stats.get_today_sessions() >= self.settings.daily_goal
     }
 
     pub fn get_current_screen(&self) -> &settings::Screen {
@@ -296,6 +1067,10 @@ impl App {
         &self.settings
     }
 
+    pub fn get_settings_file(&self) -> &settings::SettingsFile {
+        &self.settings_file
+    }
+
     pub fn get_settings_field(&self) -> &settings::SettingsField {
         &self.settings_field
     }
@@ -308,12 +1083,52 @@ impl App {
         &self.input_buffer
     }
 
-    pub fn get_long_break_count(&self) -> u64 {
-        self.long_break_count
+    pub fn get_input_error(&self) -> Option<&str> {
+        self.input_error.as_deref()
+    }
+
+    pub fn get_current_tag(&self) -> Option<&str> {
+        self.current_tag.as_deref()
+    }
+
+    /// Whole seconds until the next phase starts by itself.
+    pub fn get_auto_start_in(&self) -> Option<u64> {
+        self.auto_start_at.map(|at| {
+            let left = at.saturating_duration_since(std::time::Instant::now());
+            left.as_secs() + u64::from(left.subsec_nanos() > 0)
+        })
+    }
+
+    pub fn get_cycles_completed(&self) -> u64 {
+        self.cycles_completed
+    }
+
+    pub fn is_counting_up(&self) -> bool {
+        self.counting_up
+    }
+
+    /// Time spent in the flowtime session on the clock.
+    pub fn get_elapsed(&self) -> u64 {
+        FLOWTIME_LIMIT.saturating_sub(self.remaining_timer)
+    }
+
+    pub fn get_phase_index(&self) -> usize {
+        self.phase_index
+    }
+
+    pub fn is_away(&self) -> bool {
+        self.away
     }
 
     fn on_key_event(&mut self, key: KeyEvent) {
-        if let KeyCode::Char('?') = key.code {
+        if self.dialog.is_some() {
+            self.handle_dialog_input(key);
+            return;
+        }
+
+        if let KeyCode::Char('?') = key.code
+            && !self.editing_field
+        {
             self.show_help = !self.show_help;
             return;
         }
@@ -321,18 +1136,76 @@ impl App {
         match self.current_screen {
             settings::Screen::Timer => self.handle_timer_input(key),
             settings::Screen::Settings => self.handle_settings_input(key),
+            settings::Screen::Stats => self.handle_stats_input(key),
         }
     }
 
     fn handle_timer_input(&mut self, key: KeyEvent) {
+        if self.editing_field {
+            self.handle_tag_editing(key);
+            return;
+        }
+
         match (key.modifiers, key.code) {
             (_, KeyCode::Esc | KeyCode::Char('q'))
-            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
+            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => {
+                self.request_stop(StopAction::Quit)
+            }
             (_, KeyCode::Char('s')) => self.start_timer(),
+            (_, KeyCode::Char('c')) => self.cancel_auto_start(),
+            (_, KeyCode::Char('e')) => self.end_flow(),
             (_, KeyCode::Char('p')) => self.pause_timer(),
-            (_, KeyCode::Char('r')) => self.reset_timer(),
-            (_, KeyCode::Char('S')) => self.skip_session(),
+            (_, KeyCode::Char('+') | KeyCode::Char('=')) => {
+                self.adjust_timer(EXTEND_STEP as i64);
+            }
+            (_, KeyCode::Char('-')) => {
+                self.adjust_timer(-(EXTEND_STEP as i64));
+            }
+            (_, KeyCode::Char('r')) => self.request_stop(StopAction::Reset),
+            (_, KeyCode::Char('S')) => self.request_stop(StopAction::Skip),
             (_, KeyCode::Char('o')) => self.current_screen = settings::Screen::Settings,
+            (_, KeyCode::Char('h')) => self.current_screen = settings::Screen::Stats,
+            (_, KeyCode::Char('t')) => self.start_tag_editing(),
+            (_, KeyCode::Char('x')) => self.warnings.clear(),
+            _ => {}
+        }
+    }
+
+    fn handle_tag_editing(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.cancel_editing(),
+            KeyCode::Enter => self.save_tag(),
+            KeyCode::Backspace => {
+                self.input_buffer.pop();
+            }
+            KeyCode::Char(c) if self.input_buffer.chars().count() < cli::MAX_TAG_LENGTH => {
+                self.input_buffer.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn start_tag_editing(&mut self) {
+        self.editing_field = true;
+        self.input_buffer = self.current_tag.clone().unwrap_or_default();
+    }
+
+    fn save_tag(&mut self) {
+        // An empty tag clears it
+        self.current_tag = cli::validate_tag(&self.input_buffer).ok();
+        if let (TimerState::Work, Some(session)) = (&self.current_state, &mut self.active_session) {
+            session.set_tag(self.current_tag.clone());
+        }
+        self.editing_field = false;
+        self.input_buffer.clear();
+    }
+
+    fn handle_stats_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('h') => self.current_screen = settings::Screen::Timer,
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                self.stats_range = self.stats_range.toggle()
+            }
             _ => {}
         }
     }
@@ -351,6 +1224,16 @@ impl App {
             KeyCode::Up | KeyCode::Char('k') => self.previous_setting(),
             KeyCode::Down | KeyCode::Char('j') => self.next_setting(),
             KeyCode::Enter => self.start_editing(),
+            KeyCode::Left | KeyCode::Char('h')
+                if matches!(self.settings_field, settings::SettingsField::Profile) =>
+            {
+                self.switch_profile(false)
+            }
+            KeyCode::Right | KeyCode::Char('l')
+                if matches!(self.settings_field, settings::SettingsField::Profile) =>
+            {
+                self.switch_profile(true)
+            }
             _ => {}
         }
     }
@@ -361,40 +1244,132 @@ impl App {
             KeyCode::Enter => self.save_field(),
             KeyCode::Backspace => {
                 self.input_buffer.pop();
+                self.input_error = None;
             }
-            KeyCode::Char(c) if c.is_ascii_digit() => {
-                if self.input_buffer.len() < 3 {
-                    // Limit to 999 minutes
-                    self.input_buffer.push(c);
-                }
+            KeyCode::Char(c) if self.accepts_input(c) => {
+                self.input_buffer.push(c);
+                self.input_error = None;
             }
             _ => {}
         }
     }
 
+    /// Durations also take units and a decimal point, e.g. `1h30m` or `1.5`.
+    fn accepts_input(&self, c: char) -> bool {
+        if self.settings_field.is_duration() {
+            (c.is_ascii_digit() || matches!(c, '.' | 'h' | 'm' | 's'))
+                && self.input_buffer.len() < 8
+        } else if matches!(
+            self.settings_field,
+            settings::SettingsField::FlowtimeBreakRatio
+        ) {
+            // A fraction like 1/5 or a decimal like 0.2
+            (c.is_ascii_digit() || matches!(c, '.' | '/')) && self.input_buffer.len() < 8
+        } else {
+            // Limit to 999
+            c.is_ascii_digit() && self.input_buffer.len() < 3
+        }
+    }
+
     fn previous_setting(&mut self) {
         self.settings_field = match self.settings_field {
-            settings::SettingsField::WorkingTime => settings::SettingsField::SessionsUntilLongBreak,
+            settings::SettingsField::Profile => settings::SettingsField::FlowtimeBreakRatio,
+            settings::SettingsField::WorkingTime => settings::SettingsField::Profile,
             settings::SettingsField::BreakTime => settings::SettingsField::WorkingTime,
             settings::SettingsField::LongBreakTime => settings::SettingsField::BreakTime,
             settings::SettingsField::SessionsUntilLongBreak => {
                 settings::SettingsField::LongBreakTime
             }
+            settings::SettingsField::DailyGoal => settings::SettingsField::SessionsUntilLongBreak,
+            settings::SettingsField::SkipsAdvanceCycle => settings::SettingsField::DailyGoal,
+            settings::SettingsField::LiveApply => settings::SettingsField::SkipsAdvanceCycle,
+            settings::SettingsField::SuspendPolicy => settings::SettingsField::LiveApply,
+            settings::SettingsField::AutoStartBreaks => settings::SettingsField::SuspendPolicy,
+            settings::SettingsField::AutoStartWork => settings::SettingsField::AutoStartBreaks,
+            settings::SettingsField::AutoStartDelay => settings::SettingsField::AutoStartWork,
+            settings::SettingsField::StopAfterCycles => settings::SettingsField::AutoStartDelay,
+            settings::SettingsField::IdleTimeout => settings::SettingsField::StopAfterCycles,
+            settings::SettingsField::IdleReturn => settings::SettingsField::IdleTimeout,
+            settings::SettingsField::Flowtime => settings::SettingsField::IdleReturn,
+            settings::SettingsField::FlowtimeBreakRatio => settings::SettingsField::Flowtime,
         };
     }
 
     fn next_setting(&mut self) {
         self.settings_field = match self.settings_field {
+            settings::SettingsField::Profile => settings::SettingsField::WorkingTime,
             settings::SettingsField::WorkingTime => settings::SettingsField::BreakTime,
             settings::SettingsField::BreakTime => settings::SettingsField::LongBreakTime,
             settings::SettingsField::LongBreakTime => {
                 settings::SettingsField::SessionsUntilLongBreak
             }
-            settings::SettingsField::SessionsUntilLongBreak => settings::SettingsField::WorkingTime,
+            settings::SettingsField::SessionsUntilLongBreak => settings::SettingsField::DailyGoal,
+            settings::SettingsField::DailyGoal => settings::SettingsField::SkipsAdvanceCycle,
+            settings::SettingsField::SkipsAdvanceCycle => settings::SettingsField::LiveApply,
+            settings::SettingsField::LiveApply => settings::SettingsField::SuspendPolicy,
+            settings::SettingsField::SuspendPolicy => settings::SettingsField::AutoStartBreaks,
+            settings::SettingsField::AutoStartBreaks => settings::SettingsField::AutoStartWork,
+            settings::SettingsField::AutoStartWork => settings::SettingsField::AutoStartDelay,
+            settings::SettingsField::AutoStartDelay => settings::SettingsField::StopAfterCycles,
+            settings::SettingsField::StopAfterCycles => settings::SettingsField::IdleTimeout,
+            settings::SettingsField::IdleTimeout => settings::SettingsField::IdleReturn,
+            settings::SettingsField::IdleReturn => settings::SettingsField::Flowtime,
+            settings::SettingsField::Flowtime => settings::SettingsField::FlowtimeBreakRatio,
+            settings::SettingsField::FlowtimeBreakRatio => settings::SettingsField::Profile,
         };
     }
 
     fn start_editing(&mut self) {
+        match self.settings_field {
+            settings::SettingsField::Profile => {
+                self.switch_profile(true);
+                return;
+            }
+            settings::SettingsField::SkipsAdvanceCycle => {
+                // Toggles flip in place, there is nothing to type
+                self.settings_file.editable_layer_mut().skips_advance_cycle =
+                    Some(!self.settings.skips_advance_cycle);
+                self.persist_settings();
+                return;
+            }
+            settings::SettingsField::LiveApply => {
+                self.settings_file.editable_layer_mut().live_apply =
+                    Some(self.settings.live_apply.next());
+                self.persist_settings();
+                return;
+            }
+            settings::SettingsField::SuspendPolicy => {
+                self.settings_file.editable_layer_mut().suspend_policy =
+                    Some(self.settings.suspend_policy.next());
+                self.persist_settings();
+                return;
+            }
+            settings::SettingsField::AutoStartBreaks => {
+                self.settings_file.editable_layer_mut().auto_start_breaks =
+                    Some(!self.settings.auto_start_breaks);
+                self.persist_settings();
+                return;
+            }
+            settings::SettingsField::AutoStartWork => {
+                self.settings_file.editable_layer_mut().auto_start_work =
+                    Some(!self.settings.auto_start_work);
+                self.persist_settings();
+                return;
+            }
+            settings::SettingsField::IdleReturn => {
+                self.settings_file.editable_layer_mut().idle_return =
+                    Some(self.settings.idle_return.next());
+                self.persist_settings();
+                return;
+            }
+            settings::SettingsField::Flowtime => {
+                self.settings_file.editable_layer_mut().flowtime = Some(!self.settings.flowtime);
+                self.persist_settings();
+                return;
+            }
+            _ => {}
+        }
+
         self.editing_field = true;
         self.input_buffer = match self.settings_field {
             settings::SettingsField::WorkingTime => self.settings.working_time.to_string(),
@@ -403,38 +1378,83 @@ impl App {
             settings::SettingsField::SessionsUntilLongBreak => {
                 self.settings.sessions_until_long_break.to_string()
             }
+            settings::SettingsField::DailyGoal => self.settings.daily_goal.to_string(),
+            settings::SettingsField::AutoStartDelay => self.settings.auto_start_delay.to_string(),
+            settings::SettingsField::StopAfterCycles => self.settings.stop_after_cycles.to_string(),
+            settings::SettingsField::IdleTimeout => self.settings.idle_timeout.to_string(),
+            settings::SettingsField::FlowtimeBreakRatio => {
+                self.settings.flowtime_break_ratio.to_string()
+            }
+            settings::SettingsField::Profile
+            | settings::SettingsField::SkipsAdvanceCycle
+            | settings::SettingsField::LiveApply
+            | settings::SettingsField::SuspendPolicy
+            | settings::SettingsField::AutoStartBreaks
+            | settings::SettingsField::AutoStartWork
+            | settings::SettingsField::IdleReturn
+            | settings::SettingsField::Flowtime => String::new(),
         };
     }
 
     fn cancel_editing(&mut self) {
         self.editing_field = false;
         self.input_buffer.clear();
+        self.input_error = None;
     }
 
+    /// Invalid input keeps the editor open with the error shown under the
+    /// field.
     fn save_field(&mut self) {
-        if let Ok(value) = self.input_buffer.parse::<u64>() {
-            if value > 0 {
-                match self.settings_field {
-                    settings::SettingsField::WorkingTime => self.settings.working_time = value,
-                    settings::SettingsField::BreakTime => self.settings.break_time = value,
-                    settings::SettingsField::LongBreakTime => self.settings.long_break_time = value,
-                    settings::SettingsField::SessionsUntilLongBreak => {
-                        self.settings.sessions_until_long_break = value
-                    }
-                }
+        let layer = self.settings_file.editable_layer_mut();
+        match self
+            .settings_field
+            .set_from_input(&self.input_buffer, layer)
+        {
+            Ok(()) => {
+                self.persist_settings();
+                self.cancel_editing();
+            }
+            Err(e) => self.input_error = Some(e),
+        }
+    }
 
-                match config::Config::save_settings(&self.settings) {
-                    Ok(_) => {
-                        self.settings_saved_message = Some(std::time::Instant::now());
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to save settings: {}", e);
-                    }
-                }
+    fn switch_profile(&mut self, forward: bool) {
+        let profile = self.settings_file.cycle_profile(forward);
+        if self.settings_file.set_active_profile(profile).is_ok() {
+            self.persist_settings();
+        }
+    }
+
+    /// Saves `settings.toml` after an edit. The edited field stops being
+    /// overridden by the environment or CLI flags for the rest of the run.
+    fn persist_settings(&mut self) {
+        let old_duration = self.current_phase_duration();
+        self.overrides.clear(&self.settings_field);
+        self.settings = self.settings_file.resolve();
+        self.overrides.apply_to(&mut self.settings);
+        self.idle_source = idle::from_path(&self.settings.idle_file);
+        if !self.timer_active {
+            self.sync_cycle_state();
+            // A break earned by flowtime goes back to its usual length
+            if !self.settings.flowtime && self.flow_break.take().is_some() {
+                self.save_state();
+            }
+        }
+        let note = self.retarget_timer(old_duration);
+
+        match config::Config::save_settings(&self.settings_file) {
+            Ok(_) => {
+                let message = match note {
+                    Some(note) => format!("✓ Settings saved, {}", note),
+                    None => "✓ Settings saved!".to_string(),
+                };
+                self.settings_saved_message = Some((std::time::Instant::now(), message));
+            }
+            Err(e) => {
+                self.warnings
+                    .push(format!("Failed to save settings: {}", e));
             }
         }
-        self.editing_field = false;
-        self.input_buffer.clear();
     }
 
     // Add new methods to App:
@@ -444,7 +1464,7 @@ impl App {
                 self.stats_saved_message = Some(std::time::Instant::now());
             }
             Err(e) => {
-                eprintln!("Failed to save stats: {}", e);
+                self.warnings.push(format!("Failed to save stats: {}", e));
             }
         }
     }
@@ -453,16 +1473,78 @@ impl App {
         &self.stats
     }
 
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn get_status_message(&self) -> Option<&str> {
+        self.status_message
+            .as_ref()
+            .filter(|(shown_at, _)| shown_at.elapsed().as_secs() < STATUS_MESSAGE_SECS)
+            .map(|(_, message)| message.as_str())
+    }
+
+    fn show_status(&mut self, message: String) {
+        self.status_message = Some((std::time::Instant::now(), message));
+    }
+
+    pub fn get_stats_range(&self) -> stats::StatsRange {
+        self.stats_range
+    }
+
     fn quit(&mut self) {
-        if !self.timer_active {
-            self.app_running = false;
-            use crossterm::execute;
-            use crossterm::terminal::{Clear, ClearType};
-            let _ = execute!(std::io::stdout(), Clear(ClearType::All));
+        self.app_running = false;
+        if self.owns_socket {
+            ipc::unbind();
         }
+        use crossterm::execute;
+        use crossterm::terminal::{Clear, ClearType};
+        let _ = execute!(std::io::stdout(), Clear(ClearType::All));
     }
 }
 
+fn format_focused(session: Option<&history::ActiveSession>) -> String {
+    let focused = session.map_or(0, |session| session.focused_secs());
+    format!("{:02}:{:02} spent so far", focused / 60, focused % 60)
+}
+
+/// Asks whether to pick up the countdown left over from the last run.
+fn resume_dialog(countdown: &state::CountdownSnapshot) -> dialog::Dialog<DialogAction> {
+    let phase = match countdown.session.kind() {
+        history::SessionKind::Work => "fokus session",
+        history::SessionKind::ShortBreak => "short break",
+        history::SessionKind::LongBreak => "long break",
+    };
+    let remaining = countdown.remaining_now();
+    let status = if countdown.counting_up {
+        let elapsed = FLOWTIME_LIMIT.saturating_sub(remaining);
+        format!(
+            "{:02}:{:02} so far{}",
+            elapsed / 60,
+            elapsed % 60,
+            if countdown.paused { ", paused" } else { "" }
+        )
+    } else if remaining == 0 {
+        "It ran out while fokus was closed".to_string()
+    } else {
+        format!(
+            "{:02}:{:02} left{}",
+            remaining / 60,
+            remaining % 60,
+            if countdown.paused { ", paused" } else { "" }
+        )
+    };
+    dialog::Dialog::new(
+        "Welcome back",
+        format!("Resume the {} from last time?\n{}", phase, status),
+    )
+    .choice('y', "resume", DialogAction::Resume)
+    .choice('n', "start over", DialogAction::StartOver)
+    // Esc keeps the countdown too, starting over has to be asked for
+    .on_enter('y')
+    .on_esc('y')
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
@@ -472,13 +1554,37 @@ async fn main() -> Result<()> {
 
     // Set identifier for notifications
     #[cfg(target_os = "macos")]
-    let bundle = get_bundle_identifier_or_default("terminal");
-    set_application(&bundle).unwrap();
+    {
+        let bundle = get_bundle_identifier_or_default("terminal");
+        set_application(&bundle).unwrap();
+    }
 
     let args = Cli::parse();
+    if let Some(command) = &args.command {
+        return match command {
+            cli::Command::Stats(stats_args) => report::print_stats(stats_args),
+            cli::Command::Export(export_args) => export::export_history(export_args),
+            cli::Command::Import(import_args) => import::import_stats(import_args),
+            cli::Command::Config(cli::ConfigCommand::Show) => {
+                config::Config::show(&args.settings_layer(), args.profile.as_deref())
+            }
+            cli::Command::Extend(adjust_args) => {
+                remote(ipc::RemoteCommand::Extend(adjust_args.duration.as_secs()))
+            }
+            cli::Command::Shorten(adjust_args) => {
+                remote(ipc::RemoteCommand::Shorten(adjust_args.duration.as_secs()))
+            }
+        };
+    }
+
     let terminal = ratatui::init();
     let (app, rx) = App::new(args);
     let result = app.run(terminal, rx).await;
     ratatui::restore();
     result
 }
+
+fn remote(command: ipc::RemoteCommand) -> Result<()> {
+    println!("{}", ipc::send(command)?);
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use chrono::Local;
+use color_eyre::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::cli::StatsArgs;
+use crate::history::SessionOutcome;
+use crate::stats::SessionStats;
+
+#[derive(Debug, Serialize)]
+struct StatsReport {
+    from: String,
+    to: String,
+    total_sessions: u64,
+    skipped_sessions: u64,
+    focused_minutes: u64,
+    average_per_day: f64,
+    current_streak: u64,
+    longest_streak: u64,
+    days: Vec<DayReport>,
+    tasks: Vec<TaskReport>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct DayReport {
+    date: String,
+    sessions: u64,
+    focused_minutes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct TaskReport {
+    tag: String,
+    sessions: u64,
+    focused_minutes: u64,
+}
+
+pub fn print_stats(args: &StatsArgs) -> Result<()> {
+    let today = Local::now().date_naive();
+    let range = args.date_range(today);
+
+    let all_records = SessionStats::load_records()?;
+    let all_stats = SessionStats::from_history(&all_records);
+    let records: Vec<_> = all_records
+        .into_iter()
+        .filter(|r| range.contains(r.date()))
+        .collect();
+    let stats = SessionStats::from_history(&records);
+
+    let mut days: BTreeMap<String, DayReport> = stats
+        .get_sessions_for_range(range.end, range.days())
+        .into_iter()
+        .map(|(date, sessions)| {
+            let date = date.to_string();
+            (
+                date.clone(),
+                DayReport {
+                    date,
+                    sessions,
+                    focused_minutes: 0,
+                },
+            )
+        })
+        .collect();
+    for record in records
+        .iter()
+        .filter(|r| r.is_work() && r.outcome == SessionOutcome::Completed)
+    {
+        if let Some(day) = days.get_mut(&record.date().to_string()) {
+            day.focused_minutes += record.focused_secs / 60;
+        }
+    }
+
+    let report = StatsReport {
+        from: range.start.to_string(),
+        to: range.end.to_string(),
+        total_sessions: stats.get_total_sessions(),
+        skipped_sessions: stats.get_skipped_sessions(),
+        focused_minutes: days.values().map(|d| d.focused_minutes).sum(),
+        average_per_day: stats.get_average_per_day(range.end, range.days()),
+        current_streak: all_stats.get_current_streak(today, 1),
+        longest_streak: all_stats.get_longest_streak(1),
+        days: days.into_values().collect(),
+        tasks: stats
+            .get_top_tasks(usize::MAX)
+            .into_iter()
+            .map(|(tag, total)| TaskReport {
+                tag: tag.to_string(),
+                sessions: total.sessions,
+                focused_minutes: total.focused_secs / 60,
+            })
+            .collect(),
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_table(&report);
+    }
+    Ok(())
+}
+
+fn print_table(report: &StatsReport) {
+    println!("Fokus stats {} .. {}", report.from, report.to);
+    println!();
+    println!("{:<12} {:>8} {:>9}", "Date", "Sessions", "Focused");
+    for day in &report.days {
+        println!(
+            "{:<12} {:>8} {:>9}",
+            day.date,
+            day.sessions,
+            format_minutes(day.focused_minutes)
+        );
+    }
+    println!(
+        "{:<12} {:>8} {:>9}",
+        "Total",
+        report.total_sessions,
+        format_minutes(report.focused_minutes)
+    );
+    println!();
+    println!(
+        "Skipped: {} | Avg/day: {:.1} | Current streak: {} days | Longest streak: {} days",
+        report.skipped_sessions,
+        report.average_per_day,
+        report.current_streak,
+        report.longest_streak
+    );
+
+    if !report.tasks.is_empty() {
+        println!();
+        println!("{:<32} {:>8} {:>9}", "Task", "Sessions", "Focused");
+        for task in &report.tasks {
+            println!(
+                "{:<32} {:>8} {:>9}",
+                task.tag,
+                task.sessions,
+                format_minutes(task.focused_minutes)
+            );
+        }
+    }
+}
+
+fn format_minutes(minutes: u64) -> String {
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
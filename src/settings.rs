@@ -1,33 +1,735 @@
+use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::duration::Seconds;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
-    pub working_time: u64,
-    pub break_time: u64,
-    pub long_break_time: u64,
+    pub working_time: Seconds,
+    pub break_time: Seconds,
+    pub long_break_time: Seconds,
     pub sessions_until_long_break: u64,
+    /// Fokus sessions a day to aim for, 0 for no goal.
+    #[serde(default)]
+    pub daily_goal: u64,
+    #[serde(default = "default_skips_advance_cycle")]
+    pub skips_advance_cycle: bool,
+    #[serde(default)]
+    pub live_apply: LiveApply,
+    #[serde(default)]
+    pub suspend_policy: SuspendPolicy,
+    #[serde(default)]
+    pub auto_start_breaks: bool,
+    #[serde(default)]
+    pub auto_start_work: bool,
+    /// How long the next phase waits before it starts by itself.
+    #[serde(default = "default_auto_start_delay")]
+    pub auto_start_delay: Seconds,
+    /// Work sessions (each with its break) before auto-start stops, 0 for no
+    /// limit.
+    #[serde(default)]
+    pub stop_after_cycles: u64,
+    /// Pause the countdown after this long without input, 0 for never.
+    #[serde(default)]
+    pub idle_timeout: Seconds,
+    #[serde(default)]
+    pub idle_return: IdleReturn,
+    /// A file holding the desktop idle time in milliseconds, so input outside
+    /// the terminal counts too. Empty to watch the terminal only.
+    #[serde(default)]
+    pub idle_file: PathBuf,
+    /// Phases to step through in place of the built-in work and break
+    /// cycle, which the durations above describe.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cycle: Vec<Phase>,
+    /// Fokus sessions count up until ended by hand instead of down.
+    #[serde(default)]
+    pub flowtime: bool,
+    /// The break after a flowtime session, as a share of its focused time.
+    #[serde(default = "default_flowtime_break_ratio")]
+    pub flowtime_break_ratio: f64,
+}
+
+fn default_flowtime_break_ratio() -> f64 {
+    0.2
+}
+
+/// One step of a user-defined cycle, written as a `[[cycle]]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Phase {
+    pub name: String,
+    pub duration: Seconds,
+    /// A color name or `#rrggbb`, used for the countdown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Counts toward the stats and the daily goal like a fokus session.
+    #[serde(default)]
+    pub focus: bool,
+}
+
+impl Phase {
+    pub fn color(&self) -> Option<Color> {
+        self.color.as_deref().and_then(|color| color.parse().ok())
+    }
+}
+
+fn default_auto_start_delay() -> Seconds {
+    Seconds(10)
+}
+
+/// What editing a duration does to a countdown that is already running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LiveApply {
+    /// Keep the running countdown, the new length starts with the next phase.
+    #[default]
+    Off,
+    /// Keep the time already spent and count down to the new length.
+    Absolute,
+    /// Scale the remaining time by the change in length.
+    Proportional,
+}
+
+impl LiveApply {
+    pub fn next(&self) -> Self {
+        match self {
+            LiveApply::Off => LiveApply::Absolute,
+            LiveApply::Absolute => LiveApply::Proportional,
+            LiveApply::Proportional => LiveApply::Off,
+        }
+    }
+
+    /// Remaining seconds after the phase length changed from `old_secs` to
+    /// `new_secs` with `remaining` seconds left.
+    pub fn retarget(&self, remaining: u64, old_secs: u64, new_secs: u64) -> u64 {
+        match self {
+            LiveApply::Off => remaining,
+            LiveApply::Absolute => new_secs.saturating_sub(old_secs.saturating_sub(remaining)),
+            LiveApply::Proportional if old_secs == 0 => new_secs,
+            LiveApply::Proportional => {
+                (remaining as f64 * new_secs as f64 / old_secs as f64).round() as u64
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for LiveApply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LiveApply::Off => "next session",
+            LiveApply::Absolute => "running timer (absolute)",
+            LiveApply::Proportional => "running timer (proportional)",
+        })
+    }
+}
+
+fn default_skips_advance_cycle() -> bool {
+    true
+}
+
+/// What happens to a running countdown when the machine was asleep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuspendPolicy {
+    /// Pause the countdown where it was when the machine went to sleep.
+    #[default]
+    Pause,
+    /// Count the time asleep as elapsed, finishing the phase if it ran out.
+    Count,
+    /// Throw the phase away and record it as discarded.
+    Discard,
+}
+
+impl SuspendPolicy {
+    pub fn next(&self) -> Self {
+        match self {
+            SuspendPolicy::Pause => SuspendPolicy::Count,
+            SuspendPolicy::Count => SuspendPolicy::Discard,
+            SuspendPolicy::Discard => SuspendPolicy::Pause,
+        }
+    }
+}
+
+impl std::fmt::Display for SuspendPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SuspendPolicy::Pause => "pause the timer",
+            SuspendPolicy::Count => "count as elapsed",
+            SuspendPolicy::Discard => "discard the session",
+        })
+    }
+}
+
+/// What happens to a countdown paused for inactivity once the user is back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleReturn {
+    /// Carry on where the countdown was paused.
+    #[default]
+    Resume,
+    /// Throw the phase away and record it as discarded.
+    Discard,
+}
+
+impl IdleReturn {
+    pub fn next(&self) -> Self {
+        match self {
+            IdleReturn::Resume => IdleReturn::Discard,
+            IdleReturn::Discard => IdleReturn::Resume,
+        }
+    }
+}
+
+impl std::fmt::Display for IdleReturn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IdleReturn::Resume => "resume the timer",
+            IdleReturn::Discard => "discard the session",
+        })
+    }
+}
+
+/// Longest phase a duration setting may be set to.
+pub const MAX_DURATION: Seconds = Seconds(24 * 60 * 60);
+/// Longest wait before the next phase starts by itself.
+pub const MAX_AUTO_START_DELAY: Seconds = Seconds(60 * 60);
+/// Upper bound for the session count settings.
+pub const MAX_SESSIONS: u64 = 100;
+
+// The same rules apply to CLI flags, `settings.toml`, `FOKUS_*` variables and
+// the settings screen.
+
+pub fn validate_duration(time: Seconds) -> Result<Seconds, String> {
+    if time.as_secs() == 0 || time > MAX_DURATION {
+        Err(format!("Must be between 1s and {}", MAX_DURATION))
+    } else {
+        Ok(time)
+    }
+}
+
+pub fn validate_sessions_until_long_break(count: u64) -> Result<u64, String> {
+    if count == 0 || count > MAX_SESSIONS {
+        Err(format!("Must be between 1 and {} sessions", MAX_SESSIONS))
+    } else {
+        Ok(count)
+    }
+}
+
+/// A delay of 0 starts the next phase straight away.
+pub fn validate_auto_start_delay(delay: Seconds) -> Result<Seconds, String> {
+    if delay > MAX_AUTO_START_DELAY {
+        Err(format!("Must be between 0s and {}", MAX_AUTO_START_DELAY))
+    } else {
+        Ok(delay)
+    }
+}
+
+/// 0 keeps going until stopped by hand.
+pub fn validate_stop_after_cycles(cycles: u64) -> Result<u64, String> {
+    if cycles > MAX_SESSIONS {
+        Err(format!(
+            "Must be between 0 (off) and {} cycles",
+            MAX_SESSIONS
+        ))
+    } else {
+        Ok(cycles)
+    }
+}
+
+/// An idle timeout of 0 turns idle detection off.
+pub fn validate_idle_timeout(timeout: Seconds) -> Result<Seconds, String> {
+    if timeout > MAX_DURATION {
+        Err(format!("Must be between 0s (off) and {}", MAX_DURATION))
+    } else {
+        Ok(timeout)
+    }
+}
+
+/// Every phase needs a name, a valid duration and a color that can be shown.
+pub fn validate_cycle(cycle: &[Phase]) -> Result<(), String> {
+    for (number, phase) in cycle.iter().enumerate() {
+        if phase.name.trim().is_empty() {
+            return Err(format!("phase {} has no name", number + 1));
+        }
+        validate_duration(phase.duration).map_err(|e| format!("phase `{}`: {}", phase.name, e))?;
+        if let Some(color) = &phase.color
+            && color.parse::<Color>().is_err()
+        {
+            return Err(format!("phase `{}`: unknown color `{}`", phase.name, color));
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_flowtime_break_ratio(ratio: f64) -> Result<f64, String> {
+    if ratio > 0.0 && ratio <= 1.0 {
+        Ok(ratio)
+    } else {
+        Err("Must be above 0 and at most 1, e.g. 0.2 or 1/5".to_string())
+    }
+}
+
+/// A daily goal of 0 turns the goal off.
+pub fn validate_daily_goal(goal: u64) -> Result<u64, String> {
+    if goal > MAX_SESSIONS {
+        Err(format!(
+            "Must be between 0 (off) and {} sessions",
+            MAX_SESSIONS
+        ))
+    } else {
+        Ok(goal)
+    }
+}
+
+/// Parses durations like `25m`, `90s` or `1h30m`. A bare number is minutes.
+pub fn parse_duration(s: &str) -> Result<Seconds, String> {
+    validate_duration(s.parse()?)
+}
+
+pub fn parse_sessions_until_long_break(s: &str) -> Result<u64, String> {
+    validate_sessions_until_long_break(parse_number(s)?)
+}
+
+pub fn parse_daily_goal(s: &str) -> Result<u64, String> {
+    validate_daily_goal(parse_number(s)?)
+}
+
+pub fn parse_auto_start_delay(s: &str) -> Result<Seconds, String> {
+    validate_auto_start_delay(s.parse()?)
+}
+
+pub fn parse_stop_after_cycles(s: &str) -> Result<u64, String> {
+    validate_stop_after_cycles(parse_number(s)?)
+}
+
+pub fn parse_idle_timeout(s: &str) -> Result<Seconds, String> {
+    validate_idle_timeout(s.parse()?)
+}
+
+/// Takes a fraction like `1/5` or a decimal like `0.2`.
+pub fn parse_flowtime_break_ratio(s: &str) -> Result<f64, String> {
+    let invalid = || format!("`{}` is not a valid ratio, try 0.2 or 1/5", s);
+    let ratio = match s.trim().split_once('/') {
+        Some((numerator, denominator)) => {
+            let numerator: f64 = numerator.trim().parse().map_err(|_| invalid())?;
+            let denominator: f64 = denominator.trim().parse().map_err(|_| invalid())?;
+            numerator / denominator
+        }
+        None => s.trim().parse().map_err(|_| invalid())?,
+    };
+    validate_flowtime_break_ratio(ratio)
+}
+
+fn parse_number(s: &str) -> Result<u64, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("`{}` is not a valid number", s))
 }
 
 impl Settings {
     pub fn default() -> Self {
         Self {
-            working_time: 25,
-            break_time: 5,
-            long_break_time: 15,
+            working_time: Seconds::from_minutes(25),
+            break_time: Seconds::from_minutes(5),
+            long_break_time: Seconds::from_minutes(15),
             sessions_until_long_break: 2,
+            daily_goal: 0,
+            skips_advance_cycle: default_skips_advance_cycle(),
+            live_apply: LiveApply::default(),
+            suspend_policy: SuspendPolicy::default(),
+            auto_start_breaks: false,
+            auto_start_work: false,
+            auto_start_delay: default_auto_start_delay(),
+            stop_after_cycles: 0,
+            idle_timeout: Seconds(0),
+            idle_return: IdleReturn::default(),
+            idle_file: PathBuf::new(),
+            cycle: Vec::new(),
+            flowtime: false,
+            flowtime_break_ratio: default_flowtime_break_ratio(),
         }
     }
 
+    /// The phase at `index` of the user-defined cycle, wrapping around.
+    /// `None` when the built-in work and break cycle is in use.
+    pub fn cycle_phase(&self, index: usize) -> Option<&Phase> {
+        (!self.cycle.is_empty()).then(|| &self.cycle[index % self.cycle.len()])
+    }
+
     pub fn get_working_time_seconds(&self) -> u64 {
-        self.working_time * 60
+        self.working_time.as_secs()
     }
 
     pub fn get_break_time_seconds(&self) -> u64 {
-        self.break_time * 60
+        self.break_time.as_secs()
     }
 
     pub fn get_long_break_time_seconds(&self) -> u64 {
-        self.long_break_time * 60
+        self.long_break_time.as_secs()
+    }
+
+    /// Every setting as `(name, value)`, named as in `settings.toml`.
+    pub fn fields(&self) -> Vec<(String, String)> {
+        toml::Table::try_from(self)
+            .map(|table| {
+                table
+                    .into_iter()
+                    .map(|(name, value)| match value {
+                        toml::Value::String(value) => (name, value),
+                        value => (name, value.to_string()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A partial set of settings coming from one source. Layers are applied on
+/// top of the defaults in order: `settings.toml`, environment, CLI flags.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SettingsLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_time: Option<Seconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub break_time: Option<Seconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_break_time: Option<Seconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sessions_until_long_break: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_goal: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skips_advance_cycle: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live_apply: Option<LiveApply>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspend_policy: Option<SuspendPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_start_breaks: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_start_work: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_start_delay: Option<Seconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_after_cycles: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<Seconds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_return: Option<IdleReturn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cycle: Option<Vec<Phase>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flowtime: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flowtime_break_ratio: Option<f64>,
+}
+
+impl SettingsLayer {
+    pub fn apply_to(&self, settings: &mut Settings) {
+        if let Some(value) = self.working_time {
+            settings.working_time = value;
+        }
+        if let Some(value) = self.break_time {
+            settings.break_time = value;
+        }
+        if let Some(value) = self.long_break_time {
+            settings.long_break_time = value;
+        }
+        if let Some(value) = self.sessions_until_long_break {
+            settings.sessions_until_long_break = value;
+        }
+        if let Some(value) = self.daily_goal {
+            settings.daily_goal = value;
+        }
+        if let Some(value) = self.skips_advance_cycle {
+            settings.skips_advance_cycle = value;
+        }
+        if let Some(value) = self.live_apply {
+            settings.live_apply = value;
+        }
+        if let Some(value) = self.suspend_policy {
+            settings.suspend_policy = value;
+        }
+        if let Some(value) = self.auto_start_breaks {
+            settings.auto_start_breaks = value;
+        }
+        if let Some(value) = self.auto_start_work {
+            settings.auto_start_work = value;
+        }
+        if let Some(value) = self.auto_start_delay {
+            settings.auto_start_delay = value;
+        }
+        if let Some(value) = self.stop_after_cycles {
+            settings.stop_after_cycles = value;
+        }
+        if let Some(value) = self.idle_timeout {
+            settings.idle_timeout = value;
+        }
+        if let Some(value) = self.idle_return {
+            settings.idle_return = value;
+        }
+        if let Some(value) = &self.idle_file {
+            settings.idle_file = value.clone();
+        }
+        if let Some(value) = &self.cycle {
+            settings.cycle = value.clone();
+        }
+        if let Some(value) = self.flowtime {
+            settings.flowtime = value;
+        }
+        if let Some(value) = self.flowtime_break_ratio {
+            settings.flowtime_break_ratio = value;
+        }
+    }
+
+    /// Later layers win.
+    pub fn merge(&mut self, other: &SettingsLayer) {
+        self.working_time = other.working_time.or(self.working_time);
+        self.break_time = other.break_time.or(self.break_time);
+        self.long_break_time = other.long_break_time.or(self.long_break_time);
+        self.sessions_until_long_break = other
+            .sessions_until_long_break
+            .or(self.sessions_until_long_break);
+        self.daily_goal = other.daily_goal.or(self.daily_goal);
+        self.skips_advance_cycle = other.skips_advance_cycle.or(self.skips_advance_cycle);
+        self.live_apply = other.live_apply.or(self.live_apply);
+        self.suspend_policy = other.suspend_policy.or(self.suspend_policy);
+        self.auto_start_breaks = other.auto_start_breaks.or(self.auto_start_breaks);
+        self.auto_start_work = other.auto_start_work.or(self.auto_start_work);
+        self.auto_start_delay = other.auto_start_delay.or(self.auto_start_delay);
+        self.stop_after_cycles = other.stop_after_cycles.or(self.stop_after_cycles);
+        self.idle_timeout = other.idle_timeout.or(self.idle_timeout);
+        self.idle_return = other.idle_return.or(self.idle_return);
+        self.idle_file = other.idle_file.clone().or(self.idle_file.take());
+        self.cycle = other.cycle.clone().or(self.cycle.take());
+        self.flowtime = other.flowtime.or(self.flowtime);
+        self.flowtime_break_ratio = other.flowtime_break_ratio.or(self.flowtime_break_ratio);
+    }
+
+    /// Drops the override for a field the user just edited on the settings
+    /// screen, so the edit takes effect.
+    pub fn clear(&mut self, field: &SettingsField) {
+        match field {
+            SettingsField::Profile => {}
+            SettingsField::WorkingTime => self.working_time = None,
+            SettingsField::BreakTime => self.break_time = None,
+            SettingsField::LongBreakTime => self.long_break_time = None,
+            SettingsField::SessionsUntilLongBreak => self.sessions_until_long_break = None,
+            SettingsField::DailyGoal => self.daily_goal = None,
+            SettingsField::SkipsAdvanceCycle => self.skips_advance_cycle = None,
+            SettingsField::LiveApply => self.live_apply = None,
+            SettingsField::SuspendPolicy => self.suspend_policy = None,
+            SettingsField::AutoStartBreaks => self.auto_start_breaks = None,
+            SettingsField::AutoStartWork => self.auto_start_work = None,
+            SettingsField::AutoStartDelay => self.auto_start_delay = None,
+            SettingsField::StopAfterCycles => self.stop_after_cycles = None,
+            SettingsField::IdleTimeout => self.idle_timeout = None,
+            SettingsField::IdleReturn => self.idle_return = None,
+            SettingsField::Flowtime => self.flowtime = None,
+            SettingsField::FlowtimeBreakRatio => self.flowtime_break_ratio = None,
+        }
+    }
+
+    /// Removes values that are out of range, so the next layer down applies
+    /// instead. Returns one message per removed value.
+    pub fn drop_invalid(&mut self, source: &str) -> Vec<String> {
+        fn check<T: Copy>(
+            value: &mut Option<T>,
+            validate: fn(T) -> Result<T, String>,
+            name: &str,
+            source: &str,
+            errors: &mut Vec<String>,
+        ) {
+            if let Some(current) = *value
+                && let Err(e) = validate(current)
+            {
+                errors.push(format!("Ignoring {} from {}: {}", name, source, e));
+                *value = None;
+            }
+        }
+
+        let mut errors = Vec::new();
+        check(
+            &mut self.working_time,
+            validate_duration,
+            "working_time",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.break_time,
+            validate_duration,
+            "break_time",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.long_break_time,
+            validate_duration,
+            "long_break_time",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.sessions_until_long_break,
+            validate_sessions_until_long_break,
+            "sessions_until_long_break",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.auto_start_delay,
+            validate_auto_start_delay,
+            "auto_start_delay",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.stop_after_cycles,
+            validate_stop_after_cycles,
+            "stop_after_cycles",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.idle_timeout,
+            validate_idle_timeout,
+            "idle_timeout",
+            source,
+            &mut errors,
+        );
+        if let Some(cycle) = &self.cycle
+            && let Err(e) = validate_cycle(cycle)
+        {
+            errors.push(format!("Ignoring cycle from {}: {}", source, e));
+            self.cycle = None;
+        }
+        check(
+            &mut self.flowtime_break_ratio,
+            validate_flowtime_break_ratio,
+            "flowtime_break_ratio",
+            source,
+            &mut errors,
+        );
+        check(
+            &mut self.daily_goal,
+            validate_daily_goal,
+            "daily_goal",
+            source,
+            &mut errors,
+        );
+        errors
+    }
+
+    pub fn is_set(&self, name: &str) -> bool {
+        toml::Table::try_from(self).is_ok_and(|table| table.contains_key(name))
+    }
+}
+
+/// The contents of `settings.toml`: base settings at the top level plus any
+/// number of named profiles, each applied on top of the base.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SettingsFile {
+    #[serde(flatten)]
+    pub base: SettingsLayer,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, SettingsLayer>,
+    /// Picked with `--profile` for this run only, so it is never saved.
+    #[serde(skip)]
+    pub cli_profile: Option<String>,
+}
+
+impl SettingsFile {
+    /// Defaults, then the base settings, then the active profile.
+    pub fn resolve(&self) -> Settings {
+        let mut settings = Settings::default();
+        self.base.apply_to(&mut settings);
+        if let Some(profile) = self.active_profile_layer() {
+            profile.apply_to(&mut settings);
+        }
+        settings
+    }
+
+    pub fn drop_invalid(&mut self) -> Vec<String> {
+        let mut errors = self.base.drop_invalid("settings.toml");
+        for (name, profile) in self.profiles.iter_mut() {
+            errors.extend(profile.drop_invalid(&format!("profile `{}`", name)));
+        }
+        errors
+    }
+
+    /// The `--profile` of this run if there is one, otherwise the saved one.
+    pub fn active_profile(&self) -> Option<&str> {
+        self.cli_profile
+            .as_deref()
+            .or(self.active_profile.as_deref())
+    }
+
+    pub fn active_profile_layer(&self) -> Option<&SettingsLayer> {
+        self.active_profile()
+            .and_then(|name| self.profiles.get(name))
+    }
+
+    /// Edits go to the active profile, or to the base settings without one.
+    pub fn editable_layer_mut(&mut self) -> &mut SettingsLayer {
+        let name = self.active_profile().map(str::to_string);
+        match name.and_then(|name| self.profiles.get_mut(&name)) {
+            Some(profile) => profile,
+            None => &mut self.base,
+        }
+    }
+
+    /// Switches the saved profile, which also ends a `--profile` override.
+    pub fn set_active_profile(&mut self, name: Option<String>) -> Result<(), String> {
+        self.check_profile(name.as_deref())?;
+        self.active_profile = name;
+        self.cli_profile = None;
+        Ok(())
+    }
+
+    /// Uses a profile for this run without changing the saved one.
+    pub fn set_cli_profile(&mut self, name: String) -> Result<(), String> {
+        self.check_profile(Some(&name))?;
+        self.cli_profile = Some(name);
+        Ok(())
+    }
+
+    fn check_profile(&self, name: Option<&str>) -> Result<(), String> {
+        match name {
+            Some(name) if !self.profiles.contains_key(name) => Err(format!(
+                "Unknown profile `{}`, settings.toml has: {}",
+                name,
+                self.profile_names().join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// The profile after (or before, with `forward == false`) the active one,
+    /// where `None` stands for the base settings.
+    pub fn cycle_profile(&self, forward: bool) -> Option<String> {
+        let mut choices: Vec<Option<String>> = vec![None];
+        choices.extend(self.profile_names().into_iter().map(Some));
+
+        let current = choices
+            .iter()
+            .position(|choice| choice.as_deref() == self.active_profile())
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % choices.len()
+        } else {
+            (current + choices.len() - 1) % choices.len()
+        };
+        choices[next].clone()
     }
 }
 
@@ -35,12 +737,230 @@ impl Settings {
 pub enum Screen {
     Timer,
     Settings,
+    Stats,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum SettingsField {
+    Profile,
     WorkingTime,
     BreakTime,
     LongBreakTime,
     SessionsUntilLongBreak,
+    DailyGoal,
+    SkipsAdvanceCycle,
+    LiveApply,
+    SuspendPolicy,
+    AutoStartBreaks,
+    AutoStartWork,
+    AutoStartDelay,
+    StopAfterCycles,
+    IdleTimeout,
+    IdleReturn,
+    Flowtime,
+    FlowtimeBreakRatio,
+}
+
+impl SettingsField {
+    /// The settings screen lists the fields in declaration order.
+    pub fn row(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn is_duration(&self) -> bool {
+        matches!(
+            self,
+            SettingsField::WorkingTime
+                | SettingsField::BreakTime
+                | SettingsField::LongBreakTime
+                | SettingsField::AutoStartDelay
+                | SettingsField::IdleTimeout
+        )
+    }
+
+    /// Validates what was typed on the settings screen and stores it in `layer`.
+    pub fn set_from_input(&self, input: &str, layer: &mut SettingsLayer) -> Result<(), String> {
+        match self {
+            SettingsField::WorkingTime => layer.working_time = Some(parse_duration(input)?),
+            SettingsField::BreakTime => layer.break_time = Some(parse_duration(input)?),
+            SettingsField::LongBreakTime => layer.long_break_time = Some(parse_duration(input)?),
+            SettingsField::SessionsUntilLongBreak => {
+                layer.sessions_until_long_break = Some(parse_sessions_until_long_break(input)?)
+            }
+            SettingsField::DailyGoal => layer.daily_goal = Some(parse_daily_goal(input)?),
+            SettingsField::AutoStartDelay => {
+                layer.auto_start_delay = Some(parse_auto_start_delay(input)?)
+            }
+            SettingsField::StopAfterCycles => {
+                layer.stop_after_cycles = Some(parse_stop_after_cycles(input)?)
+            }
+            SettingsField::IdleTimeout => layer.idle_timeout = Some(parse_idle_timeout(input)?),
+            SettingsField::FlowtimeBreakRatio => {
+                layer.flowtime_break_ratio = Some(parse_flowtime_break_ratio(input)?)
+            }
+            SettingsField::Profile
+            | SettingsField::SkipsAdvanceCycle
+            | SettingsField::LiveApply
+            | SettingsField::SuspendPolicy
+            | SettingsField::AutoStartBreaks
+            | SettingsField::AutoStartWork
+            | SettingsField::IdleReturn
+            | SettingsField::Flowtime => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(toml: &str) -> SettingsLayer {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn settings_file(toml: &str) -> SettingsFile {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// Puts the layers together the way the app does: the file with its
+    /// active profile, then the environment, then the CLI flags.
+    fn effective(file: &SettingsFile, env: &SettingsLayer, cli: &SettingsLayer) -> Settings {
+        let mut overrides = env.clone();
+        overrides.merge(cli);
+        let mut settings = file.resolve();
+        overrides.apply_to(&mut settings);
+        settings
+    }
+
+    const FILE: &str = r#"
+        working_time = 30
+        break_time = 6
+        daily_goal = 4
+        active_profile = "deep"
+
+        [profiles.deep]
+        working_time = 50
+        break_time = 10
+    "#;
+
+    #[test]
+    fn defaults_apply_without_any_layer() {
+        let settings = effective(
+            &SettingsFile::default(),
+            &SettingsLayer::default(),
+            &SettingsLayer::default(),
+        );
+        assert_eq!(settings.working_time, Seconds(1500));
+        assert_eq!(settings.daily_goal, 0);
+    }
+
+    #[test]
+    fn settings_file_overrides_defaults() {
+        let mut file = settings_file(FILE);
+        file.set_active_profile(None).unwrap();
+        let settings = file.resolve();
+        assert_eq!(settings.working_time, Seconds(1800));
+        assert_eq!(settings.long_break_time, Seconds(900));
+    }
+
+    #[test]
+    fn profile_overrides_settings_file() {
+        let settings = settings_file(FILE).resolve();
+        assert_eq!(settings.working_time, Seconds(3000));
+        assert_eq!(settings.break_time, Seconds(600));
+        assert_eq!(settings.daily_goal, 4);
+    }
+
+    #[test]
+    fn environment_overrides_profile() {
+        let env = layer("working_time = 40\ndaily_goal = 6");
+        let settings = effective(&settings_file(FILE), &env, &SettingsLayer::default());
+        assert_eq!(settings.working_time, Seconds(2400));
+        assert_eq!(settings.break_time, Seconds(600));
+        assert_eq!(settings.daily_goal, 6);
+    }
+
+    #[test]
+    fn cli_overrides_environment() {
+        let env = layer("working_time = 40\ndaily_goal = 6");
+        let cli = layer("working_time = 45");
+        let settings = effective(&settings_file(FILE), &env, &cli);
+        assert_eq!(settings.working_time, Seconds(2700));
+        assert_eq!(settings.daily_goal, 6);
+    }
+
+    #[test]
+    fn invalid_profile_value_falls_back_to_settings_file() {
+        let mut file = settings_file(
+            r#"
+            working_time = 30
+            active_profile = "broken"
+
+            [profiles.broken]
+            working_time = "48h"
+            break_time = 10
+            "#,
+        );
+        let warnings = file.drop_invalid();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("working_time"), "{}", warnings[0]);
+
+        let settings = file.resolve();
+        assert_eq!(settings.working_time, Seconds(1800));
+        assert_eq!(settings.break_time, Seconds(600));
+    }
+
+    #[test]
+    fn invalid_environment_value_falls_back_to_profile() {
+        let mut env = layer("sessions_until_long_break = 0\ndaily_goal = 6");
+        let warnings = env.drop_invalid("the environment");
+        assert_eq!(warnings.len(), 1);
+
+        let settings = effective(&settings_file(FILE), &env, &SettingsLayer::default());
+        assert_eq!(settings.sessions_until_long_break, 2);
+        assert_eq!(settings.daily_goal, 6);
+    }
+
+    #[test]
+    fn editing_a_field_clears_its_override() {
+        let mut file = settings_file(FILE);
+        let mut overrides = layer("working_time = 40\nbreak_time = 3");
+
+        file.editable_layer_mut().working_time = Some(Seconds(3300));
+        overrides.clear(&SettingsField::WorkingTime);
+
+        let settings = effective(&file, &overrides, &SettingsLayer::default());
+        assert_eq!(settings.working_time, Seconds(3300));
+        assert_eq!(settings.break_time, Seconds(180));
+    }
+
+    #[test]
+    fn off_keeps_the_remaining_time() {
+        assert_eq!(LiveApply::Off.retarget(600, 1500, 3000), 600);
+    }
+
+    #[test]
+    fn absolute_keeps_the_time_spent() {
+        // 15 of 25 minutes spent, the new length is 30 or 20 minutes
+        assert_eq!(LiveApply::Absolute.retarget(600, 1500, 1800), 900);
+        assert_eq!(LiveApply::Absolute.retarget(600, 1500, 1200), 300);
+    }
+
+    #[test]
+    fn absolute_runs_out_when_more_was_spent_than_the_new_length() {
+        assert_eq!(LiveApply::Absolute.retarget(600, 1500, 600), 0);
+    }
+
+    #[test]
+    fn proportional_scales_the_remaining_time() {
+        assert_eq!(LiveApply::Proportional.retarget(600, 1500, 3000), 1200);
+        assert_eq!(LiveApply::Proportional.retarget(600, 1500, 500), 200);
+        assert_eq!(LiveApply::Proportional.retarget(1, 3, 2), 1);
+    }
+
+    #[test]
+    fn proportional_from_nothing_takes_the_new_length() {
+        assert_eq!(LiveApply::Proportional.retarget(0, 0, 300), 300);
+    }
 }
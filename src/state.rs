@@ -0,0 +1,87 @@
+use chrono::{DateTime, Local};
+use color_eyre::{Result, eyre::eyre};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+use crate::TimerState;
+use crate::config::Config;
+use crate::history::ActiveSession;
+
+/// Where the app was in the cycle, written to `state.toml` on every
+/// transition so a crash or restart can pick up from there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    pub current_state: TimerState,
+    pub long_break_count: u64,
+    /// Position in a user-defined cycle.
+    #[serde(default)]
+    pub phase_index: usize,
+    /// Break length earned by the last flowtime session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow_break_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub countdown: Option<CountdownSnapshot>,
+}
+
+/// A countdown that was on the clock when the snapshot was taken.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountdownSnapshot {
+    pub paused: bool,
+    pub remaining_secs: u64,
+    /// When a running countdown runs out. Paused ones only keep
+    /// `remaining_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Local>>,
+    pub session: ActiveSession,
+    #[serde(default)]
+    pub counting_up: bool,
+}
+
+impl CountdownSnapshot {
+    /// Seconds left now. A running countdown kept going while fokus was
+    /// closed, so it may have run out since. A flowtime session did not.
+    pub fn remaining_now(&self) -> u64 {
+        match self.deadline {
+            Some(deadline) if !self.paused && !self.counting_up => {
+                (deadline - Local::now()).num_seconds().max(0) as u64
+            }
+            _ => self.remaining_secs,
+        }
+    }
+}
+
+impl TimerSnapshot {
+    fn get_state_path() -> Result<PathBuf> {
+        Ok(Config::get_config_dir()?.join("state.toml"))
+    }
+
+    /// A damaged state file is backed up and ignored, there is nothing in it
+    /// worth failing over.
+    pub fn load() -> Result<Option<TimerSnapshot>> {
+        let path = Self::get_state_path()?;
+        let _lock = Config::lock()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        match toml::from_str(&contents) {
+            Ok(snapshot) => Ok(Some(snapshot)),
+            Err(e) => {
+                let backup_path = Config::backup_corrupt(&path)?;
+                fs::remove_file(&path)?;
+                Err(eyre!(
+                    "state.toml is invalid ({}), a copy was saved to {}",
+                    e.message(),
+                    backup_path.display()
+                ))
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::get_state_path()?;
+        let contents = toml::to_string_pretty(self)?;
+        let _lock = Config::lock()?;
+        Config::write_atomic(&path, &contents)
+    }
+}
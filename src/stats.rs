@@ -1,23 +1,32 @@
-use chrono::{Local, NaiveDate};
-use color_eyre::Result;
+use chrono::{Days, Local, NaiveDate};
+use color_eyre::{Result, eyre::eyre};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs, path::PathBuf};
 
 use crate::config::Config;
+use crate::history::{SessionHistory, SessionOutcome, SessionRecord};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Aggregate counts derived from the session history. `stats.toml` is still
+/// written as a summary for anything that reads it directly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SessionStats {
     pub total_sessions: u64,
     pub daily_sessions: HashMap<String, u64>,
+    #[serde(default)]
+    pub skipped_sessions: u64,
+    #[serde(default)]
+    pub skipped_focused_secs: u64,
+    #[serde(default)]
+    pub task_totals: HashMap<String, TaskTotal>,
+    /// Seconds actually focused in completed sessions.
+    #[serde(default)]
+    pub focused_secs: u64,
 }
 
-impl Default for SessionStats {
-    fn default() -> Self {
-        Self {
-            total_sessions: 0,
-            daily_sessions: HashMap::new(),
-        }
-    }
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskTotal {
+    pub sessions: u64,
+    pub focused_secs: u64,
 }
 
 impl SessionStats {
@@ -25,12 +34,70 @@ impl SessionStats {
         Ok(Config::get_config_dir()?.join("stats.toml"))
     }
 
+    /// For the headless commands, recovery warnings go to stderr.
     pub fn load_stats() -> Result<SessionStats> {
+        let (stats, warnings) = Self::load_stats_with_warnings()?;
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(stats)
+    }
+
+    /// Loads the history, recovering damaged files instead of failing. The
+    /// warnings describe anything that had to be recovered.
+    pub fn load_stats_with_warnings() -> Result<(SessionStats, Vec<String>)> {
+        let (records, warnings) = Self::load_records_with_warnings()?;
+        Ok((Self::from_history(&records), warnings))
+    }
+
+    /// Every session in the history, for the headless commands. Recovery
+    /// warnings go to stderr.
+    pub fn load_records() -> Result<Vec<SessionRecord>> {
+        let (records, warnings) = Self::load_records_with_warnings()?;
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(records)
+    }
+
+    /// Like `SessionHistory::load_or_recover`, but an old stats.toml without
+    /// a history log is migrated into one first.
+    fn load_records_with_warnings() -> Result<(Vec<SessionRecord>, Vec<String>)> {
+        let _lock = Config::lock()?;
+        let mut warnings = Vec::new();
+
+        if !SessionHistory::exists()? {
+            let legacy = match Self::load_summary() {
+                Ok(legacy) => legacy,
+                Err(e) => {
+                    warnings.push(e.to_string());
+                    SessionStats::default()
+                }
+            };
+            let records = SessionHistory::migrate_daily_counts(&legacy.daily_sessions)?;
+            return Ok((records, warnings));
+        }
+
+        let (records, warning) = SessionHistory::load_or_recover()?;
+        warnings.extend(warning);
+        Ok((records, warnings))
+    }
+
+    fn load_summary() -> Result<SessionStats> {
         let stats_path = Self::get_stats_path()?;
         if stats_path.exists() {
-            let stats_str = fs::read_to_string(stats_path)?;
-            let stats: SessionStats = toml::from_str(&stats_str)?;
-            Ok(stats)
+            let stats_str = fs::read_to_string(&stats_path)?;
+            match toml::from_str(&stats_str) {
+                Ok(stats) => Ok(stats),
+                Err(e) => {
+                    let backup_path = Config::backup_corrupt(&stats_path)?;
+                    Err(eyre!(
+                        "stats.toml is invalid ({}), a copy was saved to {}",
+                        e.message(),
+                        backup_path.display()
+                    ))
+                }
+            }
         } else {
             Ok(SessionStats::default())
         }
@@ -39,20 +106,59 @@ impl SessionStats {
     pub fn save_stats(stats: &SessionStats) -> Result<()> {
         let stats_path = Self::get_stats_path()?;
         let stats_str = toml::to_string_pretty(stats)?;
-        fs::write(stats_path, stats_str)?;
-        Ok(())
+        let _lock = Config::lock()?;
+        Config::backup_if_unreadable::<SessionStats>(&stats_path)?;
+        Config::write_atomic(&stats_path, &stats_str)
     }
 
-    pub fn get_today_sessions(&self) -> u64 {
-        let today = Local::now().date_naive().to_string();
-        self.daily_sessions.get(&today).copied().unwrap_or(0)
+    pub fn from_history(records: &[SessionRecord]) -> Self {
+        let mut stats = Self::default();
+        for record in records {
+            stats.add_record(record);
+        }
+        stats
+    }
+
+    pub fn add_record(&mut self, record: &SessionRecord) {
+        if !record.is_work() {
+            return;
+        }
+
+        match record.outcome {
+            SessionOutcome::Completed => {
+                self.total_sessions += 1;
+                self.focused_secs += record.focused_secs;
+                *self
+                    .daily_sessions
+                    .entry(record.date().to_string())
+                    .or_insert(0) += 1;
+
+                if let Some(tag) = &record.tag {
+                    let total = self.task_totals.entry(tag.clone()).or_default();
+                    total.sessions += 1;
+                    total.focused_secs += record.focused_secs;
+                }
+            }
+            SessionOutcome::Skipped => {
+                self.skipped_sessions += 1;
+                self.skipped_focused_secs += record.focused_secs;
+            }
+            SessionOutcome::Discarded => {}
+        }
     }
 
-    pub fn increment_session(&mut self) {
-        self.total_sessions += 1;
+    /// Appends the record to the history log and reloads the counts, picking
+    /// up anything other running instances recorded in the meantime.
+    pub fn record_session(&mut self, record: SessionRecord) -> Result<()> {
+        let _lock = Config::lock()?;
+        SessionHistory::append(std::slice::from_ref(&record))?;
+        *self = Self::from_history(&SessionHistory::load()?);
+        Ok(())
+    }
 
+    pub fn get_today_sessions(&self) -> u64 {
         let today = Local::now().date_naive().to_string();
-        *self.daily_sessions.entry(today).or_insert(0) += 1;
+        self.daily_sessions.get(&today).copied().unwrap_or(0)
     }
 
     pub fn get_sessions_for_date(&self, date: NaiveDate) -> u64 {
@@ -63,4 +169,195 @@ impl SessionStats {
     pub fn get_total_sessions(&self) -> u64 {
         self.total_sessions
     }
+
+    pub fn get_focused_minutes(&self) -> u64 {
+        self.focused_secs / 60
+    }
+
+    pub fn get_skipped_sessions(&self) -> u64 {
+        self.skipped_sessions
+    }
+
+    pub fn get_skipped_focused_minutes(&self) -> u64 {
+        self.skipped_focused_secs / 60
+    }
+
+    /// Tasks ordered by focused time, busiest first.
+    pub fn get_top_tasks(&self, limit: usize) -> Vec<(&str, &TaskTotal)> {
+        let mut tasks: Vec<(&str, &TaskTotal)> = self
+            .task_totals
+            .iter()
+            .map(|(tag, total)| (tag.as_str(), total))
+            .collect();
+        tasks.sort_by(|a, b| b.1.focused_secs.cmp(&a.1.focused_secs).then(a.0.cmp(b.0)));
+        tasks.truncate(limit);
+        tasks
+    }
+
+    /// Per-day counts for the `days` days ending on `end`, oldest first.
+    pub fn get_sessions_for_range(&self, end: NaiveDate, days: u64) -> Vec<(NaiveDate, u64)> {
+        (0..days)
+            .rev()
+            .filter_map(|offset| end.checked_sub_days(Days::new(offset)))
+            .map(|date| (date, self.get_sessions_for_date(date)))
+            .collect()
+    }
+
+    pub fn get_average_per_day(&self, end: NaiveDate, days: u64) -> f64 {
+        if days == 0 {
+            return 0.0;
+        }
+        let total: u64 = self
+            .get_sessions_for_range(end, days)
+            .iter()
+            .map(|(_, count)| count)
+            .sum();
+        total as f64 / days as f64
+    }
+
+    /// Consecutive days with at least `min_sessions`, counting back from
+    /// `today`. Today only breaks the streak once it is over.
+    pub fn get_current_streak(&self, today: NaiveDate, min_sessions: u64) -> u64 {
+        let mut date = if self.get_sessions_for_date(today) >= min_sessions {
+            today
+        } else {
+            match today.pred_opt() {
+                Some(date) => date,
+                None => return 0,
+            }
+        };
+
+        let mut streak = 0;
+        while self.get_sessions_for_date(date) >= min_sessions {
+            streak += 1;
+            match date.pred_opt() {
+                Some(prev) => date = prev,
+                None => break,
+            }
+        }
+        streak
+    }
+
+    pub fn get_longest_streak(&self, min_sessions: u64) -> u64 {
+        let mut dates: Vec<NaiveDate> = self
+            .daily_sessions
+            .iter()
+            .filter(|(_, count)| **count >= min_sessions)
+            .filter_map(|(date, _)| date.parse().ok())
+            .collect();
+        dates.sort();
+
+        let mut longest = 0;
+        let mut streak = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in dates {
+            streak = match previous {
+                Some(prev) if prev.succ_opt() == Some(date) => streak + 1,
+                _ => 1,
+            };
+            longest = longest.max(streak);
+            previous = Some(date);
+        }
+        longest
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsRange {
+    Week,
+    Month,
+}
+
+impl StatsRange {
+    pub fn days(&self) -> u64 {
+        match self {
+            StatsRange::Week => 7,
+            StatsRange::Month => 30,
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            StatsRange::Week => StatsRange::Month,
+            StatsRange::Month => StatsRange::Week,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(days: &[(&str, u64)]) -> SessionStats {
+        SessionStats {
+            daily_sessions: days
+                .iter()
+                .map(|(date, count)| (date.to_string(), *count))
+                .collect(),
+            ..SessionStats::default()
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn current_streak_counts_back_from_today() {
+        let stats = stats(&[("2025-03-08", 2), ("2025-03-09", 1), ("2025-03-10", 3)]);
+        assert_eq!(stats.get_current_streak(date("2025-03-10"), 1), 3);
+    }
+
+    #[test]
+    fn current_streak_survives_today_until_it_is_over() {
+        let stats = stats(&[("2025-03-08", 2), ("2025-03-09", 1)]);
+        assert_eq!(stats.get_current_streak(date("2025-03-10"), 1), 2);
+    }
+
+    #[test]
+    fn current_streak_ends_at_a_missed_day() {
+        let stats = stats(&[("2025-03-07", 2), ("2025-03-09", 1), ("2025-03-10", 1)]);
+        assert_eq!(stats.get_current_streak(date("2025-03-10"), 1), 2);
+        assert_eq!(stats.get_current_streak(date("2025-03-11"), 1), 2);
+        assert_eq!(stats.get_current_streak(date("2025-03-12"), 1), 0);
+    }
+
+    #[test]
+    fn current_streak_needs_the_minimum_every_day() {
+        let stats = stats(&[("2025-03-08", 4), ("2025-03-09", 2), ("2025-03-10", 4)]);
+        assert_eq!(stats.get_current_streak(date("2025-03-10"), 4), 1);
+        assert_eq!(stats.get_current_streak(date("2025-03-10"), 2), 3);
+    }
+
+    #[test]
+    fn current_streak_crosses_month_boundaries() {
+        let stats = stats(&[("2025-02-28", 1), ("2025-03-01", 1)]);
+        assert_eq!(stats.get_current_streak(date("2025-03-01"), 1), 2);
+    }
+
+    #[test]
+    fn longest_streak_finds_the_longest_run() {
+        let stats = stats(&[
+            ("2025-01-01", 1),
+            ("2025-01-02", 1),
+            ("2025-01-04", 1),
+            ("2025-01-05", 1),
+            ("2025-01-06", 1),
+            ("2025-01-08", 1),
+        ]);
+        assert_eq!(stats.get_longest_streak(1), 3);
+    }
+
+    #[test]
+    fn longest_streak_skips_days_under_the_minimum() {
+        let stats = stats(&[("2025-01-01", 3), ("2025-01-02", 1), ("2025-01-03", 3)]);
+        assert_eq!(stats.get_longest_streak(3), 1);
+        assert_eq!(stats.get_longest_streak(1), 3);
+    }
+
+    #[test]
+    fn longest_streak_of_nothing_is_zero() {
+        assert_eq!(stats(&[]).get_longest_streak(1), 0);
+        assert_eq!(stats(&[("2025-01-01", 0)]).get_longest_streak(1), 0);
+    }
 }
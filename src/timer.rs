@@ -1,28 +1,321 @@
+use std::time::SystemTime;
 use tokio::{
-    sync::{broadcast, mpsc::Sender},
-    time::{Duration, sleep},
+    sync::mpsc::{Receiver, Sender},
+    time::{Duration, Instant, sleep_until},
 };
 
-pub async fn countdown(seconds: u64, tx: Sender<u64>, mut running_rx: broadcast::Receiver<bool>) {
-    let mut remaining = seconds;
-    let mut is_running = true;
+/// Sent from the app to the timer task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerCommand {
+    /// Starts a countdown of the given seconds, replacing any current one.
+    Start(u64),
+    Pause,
+    Resume,
+    AddTime(u64),
+    /// Takes time off the countdown, finishing it if nothing is left.
+    Subtract(u64),
+    /// Stops the countdown without finishing it.
+    Reset,
+    /// Stops the countdown, the app moves on to the next phase itself.
+    Skip,
+}
+
+/// Sent from the timer task to the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerEvent {
+    /// `elapsed` is how many seconds actually counted down since the previous
+    /// tick, so adjustments are never mistaken for time spent.
+    Tick {
+        remaining: u64,
+        elapsed: u64,
+    },
+    Paused {
+        remaining: u64,
+    },
+    Resumed {
+        remaining: u64,
+    },
+    Finished,
+    /// The wall clock jumped `secs` ahead of the countdown, most likely
+    /// because the machine was asleep. The countdown pauses itself and the app
+    /// decides what to do with it.
+    Suspended {
+        secs: u64,
+        remaining: u64,
+    },
+    /// The remaining time was changed by `AddTime` or `Subtract`.
+    Extended {
+        remaining: u64,
+    },
+}
+
+/// A wall clock jump this large between two ticks counts as a suspend rather
+/// than clock drift or an NTP correction.
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// The countdown is kept as a deadline while running and as the exact time
+/// left while paused, so neither scheduling jitter nor pausing mid-second
+/// shifts when it ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Running { deadline: Instant },
+    Paused { left: Duration },
+}
+
+impl State {
+    fn left(&self, now: Instant) -> Duration {
+        match *self {
+            State::Idle => Duration::ZERO,
+            State::Running { deadline } => deadline.saturating_duration_since(now),
+            State::Paused { left } => left,
+        }
+    }
+}
+
+/// Whole seconds shown to the user, rounded up so the countdown starts at
+/// the full length and only reaches zero when it is over.
+fn whole_secs(left: Duration) -> u64 {
+    left.as_secs() + u64::from(left.subsec_nanos() > 0)
+}
+
+/// Runs for the lifetime of the app, one countdown at a time. Returns once
+/// the app drops its command sender.
+pub async fn run(mut commands: Receiver<TimerCommand>, events: Sender<TimerEvent>) {
+    let mut state = State::Idle;
+    // The whole seconds last reported, to tell how many counted down since
+    let mut reported: u64 = 0;
 
-    while remaining > 0 {
-        if is_running {
+    loop {
+        let command = if let State::Running { deadline } = state {
+            // Wake up when the shown seconds next change
+            let slept_at = (Instant::now(), SystemTime::now());
+            let shown = whole_secs(deadline.saturating_duration_since(slept_at.0));
+            let next_tick = deadline - Duration::from_secs(shown.saturating_sub(1));
             tokio::select! {
-                Ok(running) = running_rx.recv() => {
-                    is_running = running;
-                }
-                _ = sleep(Duration::from_secs(1)) => {
-                    remaining -= 1;
-                    let _ = tx.send(remaining).await;
+                command = commands.recv() => command,
+                _ = sleep_until(next_tick) => {
+                    // The monotonic clock stands still while the machine is
+                    // asleep, the wall clock does not
+                    let wall = SystemTime::now()
+                        .duration_since(slept_at.1)
+                        .unwrap_or_default();
+                    let suspended = wall.saturating_sub(slept_at.0.elapsed());
+                    if suspended >= SUSPEND_THRESHOLD {
+                        state = State::Paused {
+                            left: state.left(Instant::now()),
+                        };
+                        let _ = events
+                            .send(TimerEvent::Suspended {
+                                secs: suspended.as_secs(),
+                                remaining: reported,
+                            })
+                            .await;
+                        continue;
+                    }
+
+                    let remaining = whole_secs(state.left(Instant::now()));
+                    let elapsed = reported.saturating_sub(remaining);
+                    reported = remaining;
+                    if elapsed > 0 {
+                        let _ = events.send(TimerEvent::Tick { remaining, elapsed }).await;
+                    }
+                    if remaining == 0 {
+                        state = State::Idle;
+                        let _ = events.send(TimerEvent::Finished).await;
+                    }
+                    continue;
                 }
             }
         } else {
-            if let Ok(running) = running_rx.recv().await {
-                is_running = running;
+            commands.recv().await
+        };
+
+        let Some(command) = command else {
+            return;
+        };
+        let now = Instant::now();
+        let event = match (command, state) {
+            (TimerCommand::Start(0), _) => {
+                state = State::Idle;
+                Some(TimerEvent::Finished)
+            }
+            (TimerCommand::Start(secs), _) => {
+                state = State::Running {
+                    deadline: now + Duration::from_secs(secs),
+                };
+                reported = secs;
+                None
+            }
+            (TimerCommand::Pause, State::Running { .. }) => {
+                state = State::Paused {
+                    left: state.left(now),
+                };
+                Some(TimerEvent::Paused {
+                    remaining: reported,
+                })
+            }
+            (TimerCommand::Resume, State::Paused { left }) => {
+                state = State::Running {
+                    deadline: now + left,
+                };
+                Some(TimerEvent::Resumed {
+                    remaining: reported,
+                })
+            }
+            (TimerCommand::AddTime(secs), State::Running { deadline }) => {
+                state = State::Running {
+                    deadline: deadline + Duration::from_secs(secs),
+                };
+                reported = whole_secs(state.left(now));
+                Some(TimerEvent::Extended {
+                    remaining: reported,
+                })
             }
+            (TimerCommand::AddTime(secs), State::Paused { left }) => {
+                state = State::Paused {
+                    left: left + Duration::from_secs(secs),
+                };
+                reported = whole_secs(state.left(now));
+                Some(TimerEvent::Extended {
+                    remaining: reported,
+                })
+            }
+            (TimerCommand::Subtract(secs), State::Running { .. } | State::Paused { .. }) => {
+                let left = state.left(now).saturating_sub(Duration::from_secs(secs));
+                if left.is_zero() {
+                    state = State::Idle;
+                    Some(TimerEvent::Finished)
+                } else {
+                    state = match state {
+                        State::Paused { .. } => State::Paused { left },
+                        _ => State::Running {
+                            deadline: now + left,
+                        },
+                    };
+                    reported = whole_secs(left);
+                    Some(TimerEvent::Extended {
+                        remaining: reported,
+                    })
+                }
+            }
+            (TimerCommand::Reset | TimerCommand::Skip, _) => {
+                state = State::Idle;
+                None
+            }
+            _ => None,
+        };
+        if let Some(event) = event {
+            let _ = events.send(event).await;
         }
     }
-    let _ = tx.send(0).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{sync::mpsc, time::sleep};
+
+    fn spawn_timer() -> (Sender<TimerCommand>, Receiver<TimerEvent>) {
+        let (commands_tx, commands_rx) = mpsc::channel(100);
+        let (events_tx, events_rx) = mpsc::channel(100);
+        tokio::spawn(run(commands_rx, events_tx));
+        (commands_tx, events_rx)
+    }
+
+    /// Reads events until `Finished`, returning the seconds counted down by
+    /// the ticks and every other event on the way.
+    async fn until_finished(events: &mut Receiver<TimerEvent>) -> (u64, Vec<TimerEvent>) {
+        let mut counted = 0;
+        let mut others = Vec::new();
+        while let Some(event) = events.recv().await {
+            match event {
+                TimerEvent::Tick { elapsed, .. } => counted += elapsed,
+                TimerEvent::Finished => return (counted, others),
+                event => others.push(event),
+            }
+        }
+        panic!("the timer stopped without finishing");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn finishes_exactly_at_the_deadline() {
+        let (commands, mut events) = spawn_timer();
+        let started = Instant::now();
+        commands.send(TimerCommand::Start(1500)).await.unwrap();
+
+        let (counted, others) = until_finished(&mut events).await;
+        assert_eq!(started.elapsed(), Duration::from_secs(1500));
+        assert_eq!(counted, 1500);
+        assert!(others.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pausing_mid_second_keeps_the_partial_second() {
+        let (commands, mut events) = spawn_timer();
+        let started = Instant::now();
+        commands.send(TimerCommand::Start(10)).await.unwrap();
+
+        sleep(Duration::from_millis(2500)).await;
+        commands.send(TimerCommand::Pause).await.unwrap();
+        sleep(Duration::from_secs(100)).await;
+        commands.send(TimerCommand::Resume).await.unwrap();
+
+        let (counted, others) = until_finished(&mut events).await;
+        // 2.5s before the pause and the remaining 7.5s after it
+        assert_eq!(started.elapsed(), Duration::from_secs(110));
+        assert_eq!(counted, 10);
+        assert_eq!(
+            others,
+            vec![
+                TimerEvent::Paused { remaining: 8 },
+                TimerEvent::Resumed { remaining: 8 },
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn add_time_moves_the_deadline_without_counting_as_elapsed() {
+        let (commands, mut events) = spawn_timer();
+        let started = Instant::now();
+        commands.send(TimerCommand::Start(10)).await.unwrap();
+
+        sleep(Duration::from_millis(3500)).await;
+        commands.send(TimerCommand::AddTime(5)).await.unwrap();
+
+        let (counted, others) = until_finished(&mut events).await;
+        assert_eq!(started.elapsed(), Duration::from_secs(15));
+        assert_eq!(counted, 15);
+        assert_eq!(others, vec![TimerEvent::Extended { remaining: 12 }]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn subtract_moves_the_deadline_without_counting_as_elapsed() {
+        let (commands, mut events) = spawn_timer();
+        let started = Instant::now();
+        commands.send(TimerCommand::Start(10)).await.unwrap();
+
+        sleep(Duration::from_millis(3500)).await;
+        commands.send(TimerCommand::Subtract(4)).await.unwrap();
+
+        let (counted, others) = until_finished(&mut events).await;
+        assert_eq!(started.elapsed(), Duration::from_secs(6));
+        assert_eq!(counted, 6);
+        assert_eq!(others, vec![TimerEvent::Extended { remaining: 3 }]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn subtracting_everything_left_finishes() {
+        let (commands, mut events) = spawn_timer();
+        let started = Instant::now();
+        commands.send(TimerCommand::Start(10)).await.unwrap();
+
+        sleep(Duration::from_millis(3500)).await;
+        commands.send(TimerCommand::Subtract(60)).await.unwrap();
+
+        let (counted, others) = until_finished(&mut events).await;
+        assert_eq!(started.elapsed(), Duration::from_millis(3500));
+        assert_eq!(counted, 3);
+        assert!(others.is_empty());
+    }
 }
@@ -1,19 +1,26 @@
 use crate::{
-    App, TimerState,
+    App,
+    dialog::Dialog,
     settings::{Screen, Settings, SettingsField},
+    stats::StatsRange,
 };
+use chrono::{Datelike, Days, Local};
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, BorderType, Borders, Clear, Gauge, List, ListItem,
+        ListState, Paragraph, Wrap,
+    },
 };
 
 pub fn draw(app: &App, frame: &mut Frame) {
     match app.get_current_screen() {
         Screen::Timer => draw_timer_screen(app, frame),
         Screen::Settings => draw_settings_screen(app, frame),
+        Screen::Stats => draw_stats_screen(app, frame),
     }
 }
 
@@ -33,21 +40,24 @@ fn draw_timer_screen(app: &App, frame: &mut Frame) {
         width: area.width.saturating_sub(2),
         height: area.height.saturating_sub(2),
     };
+    let inner_area = draw_warning_banner(app, frame, inner_area);
+    let inner_area = draw_status_line(app, frame, inner_area);
 
     if app.remaining_timer == 0 && !app.countdown_running {
-        let controls_text = "'s' start | 'S' skip | 'o' settings | 'q' quit | '?' hide help";
+        let controls_text =
+            "'s' start | 'S' skip | 't' task | 'o' settings | 'h' stats | 'q' quit | '?' hide help";
         let constraints = if app.show_help {
             let controls_height = calculate_text_height(controls_text, inner_area.width);
             vec![
                 Constraint::Min(0),
-                Constraint::Length(6),
+                Constraint::Length(7),
                 Constraint::Min(0),
                 Constraint::Length(controls_height),
             ]
         } else {
             vec![
                 Constraint::Min(0),
-                Constraint::Length(6),
+                Constraint::Length(7),
                 Constraint::Min(0),
             ]
         };
@@ -61,35 +71,66 @@ fn draw_timer_screen(app: &App, frame: &mut Frame) {
             working_time,
             break_time,
             long_break_time,
+            cycle,
+            flowtime,
+            flowtime_break_ratio,
             ..
         } = app.get_settings();
 
+        let durations = if cycle.is_empty() && *flowtime {
+            format!(
+                "Fokus duration: open-ended, 'e' ends it\nBreak duration: {:.0}% of the focused time",
+                flowtime_break_ratio * 100.0
+            )
+        } else if cycle.is_empty() {
+            format!(
+                "Fokus duration: {}\nBreak duration: {} / {}",
+                working_time, break_time, long_break_time
+            )
+        } else {
+            format!(
+                "Cycle: {}\nPhase {} of {}",
+                cycle
+                    .iter()
+                    .map(|phase| format!("{} {}", phase.name, phase.duration))
+                    .collect::<Vec<_>>()
+                    .join(" → "),
+                app.get_phase_index() % cycle.len() + 1,
+                cycle.len()
+            )
+        };
+        let phase = app.current_phase().name.to_lowercase();
+        let mut start_line = match app.get_auto_start_in() {
+            Some(secs) => format!(
+                "Starting the {} in {}s | 's' start now | 'c' cancel",
+                phase, secs
+            ),
+            None => format!("Press 's' to start {}", phase),
+        };
+        let cycles = app.get_settings().stop_after_cycles;
+        if cycles > 0 {
+            start_line.push_str(&format!(
+                "\nCycle {} of {}",
+                app.get_cycles_completed() + 1,
+                cycles
+            ));
+        }
+
         let content = format!(
-            "Fokus duration: {} minutes\n\
-            Break duration: {} / {} minutes\n\
-            Sessions completed: {} (today: {})\n\n\
-            Press 's' to start {}",
-            working_time,
-            break_time,
-            long_break_time,
+            "{}\n\
+            Sessions completed: {} (today: {}) | skipped: {} ({} min focused)\n\
+            {}\n\n\
+            {}",
+            durations,
             app.stats.get_total_sessions(),
             app.stats.get_today_sessions(),
-            match app.current_state {
-                TimerState::Work => "fokus session",
-                TimerState::Break => {
-                    let settings = app.get_settings();
-                    let count_until_long_break = app.get_long_break_count();
-                    if count_until_long_break > 0
-                        && count_until_long_break % settings.sessions_until_long_break == 0
-                    {
-                        "long break"
-                    } else {
-                        "short break"
-                    }
-                }
-            }
+            app.stats.get_skipped_sessions(),
+            app.stats.get_skipped_focused_minutes(),
+            task_line(app),
+            start_line
         );
         frame.render_widget(Paragraph::new(content).centered(), chunks[1]);
+        draw_goal_gauge(app, frame, chunks[2]);
 
         if app.show_help {
             frame.render_widget(
@@ -101,22 +142,32 @@ fn draw_timer_screen(app: &App, frame: &mut Frame) {
             );
         }
     } else {
-        let controls_text = match app.countdown_running {
-            true => "'p' pause | 'S' skip | 'o' settings | 'q' quit | '?' hide help",
-            false => "'p' resume | 'r' reset | 'S' skip | 'o' settings | 'q' quit | '?' hide help",
+        let controls_text = match (app.is_counting_up(), app.countdown_running) {
+            (true, true) => {
+                "'e' end | 'p' pause | 'r' reset | 'S' skip | 't' task | 'o' settings | 'h' stats | 'q' quit | '?' hide help"
+            }
+            (true, false) => {
+                "'e' end | 'p' resume | 'r' reset | 'S' skip | 't' task | 'o' settings | 'h' stats | 'q' quit | '?' hide help"
+            }
+            (false, true) => {
+                "'p' pause | '+/-' 5 min | 'r' reset | 'S' skip | 't' task | 'o' settings | 'h' stats | 'q' quit | '?' hide help"
+            }
+            (false, false) => {
+                "'p' resume | '+/-' 5 min | 'r' reset | 'S' skip | 't' task | 'o' settings | 'h' stats | 'q' quit | '?' hide help"
+            }
         };
         let constraints = if app.show_help {
             let controls_height = calculate_text_height(controls_text, inner_area.width);
             vec![
                 Constraint::Min(0),
-                Constraint::Length(4),
+                Constraint::Length(5),
                 Constraint::Min(0),
                 Constraint::Length(controls_height),
             ]
         } else {
             vec![
                 Constraint::Min(0),
-                Constraint::Length(4),
+                Constraint::Length(5),
                 Constraint::Min(0),
             ]
         };
@@ -126,40 +177,42 @@ fn draw_timer_screen(app: &App, frame: &mut Frame) {
             .constraints(constraints)
             .split(inner_area);
 
-        let session_info = match app.current_state {
-            TimerState::Work => "🎧 Fokus Session",
-            TimerState::Break => {
-                let settings = app.get_settings();
-                let count_until_long_break = app.get_long_break_count();
-                if count_until_long_break > 0
-                    && count_until_long_break % settings.sessions_until_long_break == 0
-                {
-                    "☕ Long Break"
-                } else {
-                    "☕ Short Break"
-                }
-            }
-        };
+        let phase = app.current_phase();
+        let session_info = format!("{} {}", if phase.focus { "🎧" } else { "☕" }, phase.name);
 
+        // Flowtime sessions show the time spent instead of the time left
+        let clock = if app.is_counting_up() {
+            app.get_elapsed()
+        } else {
+            app.remaining_timer
+        };
         let timer_content = format!(
-            "{}\n\n{}",
+            "{}\n{}\n\n{}",
             session_info,
-            if app.countdown_running {
+            task_line(app),
+            if app.countdown_running && app.is_counting_up() {
+                format!("⏱ Time spent: {:02}:{:02}", clock / 60, clock % 60)
+            } else if app.countdown_running {
+                format!("⏳ Time remaining: {:02}:{:02}", clock / 60, clock % 60)
+            } else if app.is_away() {
                 format!(
-                    "⏳ Time remaining: {:02}:{:02}",
-                    app.remaining_timer / 60,
-                    app.remaining_timer % 60
+                    "💤 Away, press any key: {:02}:{:02}",
+                    clock / 60,
+                    clock % 60
                 )
             } else {
-                format!(
-                    "⏸ Paused: {:02}:{:02}",
-                    app.remaining_timer / 60,
-                    app.remaining_timer % 60
-                )
+                format!("⏸ Paused: {:02}:{:02}", clock / 60, clock % 60)
             }
         );
 
-        frame.render_widget(Paragraph::new(timer_content).centered(), chunks[1]);
+        let style = phase
+            .color()
+            .map_or_else(Style::default, |color| Style::default().fg(color));
+        frame.render_widget(
+            Paragraph::new(timer_content).centered().style(style),
+            chunks[1],
+        );
+        draw_goal_gauge(app, frame, chunks[2]);
 
         if app.show_help {
             frame.render_widget(
@@ -171,6 +224,128 @@ fn draw_timer_screen(app: &App, frame: &mut Frame) {
             );
         }
     };
+
+    if let Some(dialog) = app.get_dialog() {
+        draw_dialog(dialog, frame, inner_area);
+    }
+}
+
+/// A modal box centered over `area` with the dialog's choices underneath.
+fn draw_dialog<A: Copy>(dialog: &Dialog<A>, frame: &mut Frame, area: Rect) {
+    let text = format!("{}\n\n{}", dialog.message, dialog.hint());
+    let width = 56.min(area.width);
+    let text_height: u16 = text
+        .lines()
+        .map(|line| calculate_text_height(line, width.saturating_sub(2)))
+        .sum();
+    let height = (text_height + 2).min(area.height); // +2 for borders
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(text)
+            .centered()
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::bordered()
+                    .title(Line::from(dialog.title.as_str()).bold().yellow())
+                    .border_type(BorderType::Rounded),
+            ),
+        popup,
+    );
+}
+
+/// Renders load/save problems and other failures above the timer and returns
+/// the area left over.
+fn draw_warning_banner(app: &App, frame: &mut Frame, area: Rect) -> Rect {
+    let warnings = app.get_warnings();
+    if warnings.is_empty() {
+        return area;
+    }
+
+    let text = format!("⚠ {} | 'x' to dismiss", warnings.join(" | "));
+    let height = calculate_text_height(&text, area.width).min(area.height);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+
+    frame.render_widget(
+        Paragraph::new(text)
+            .centered()
+            .style(Style::default().fg(Color::White).bg(Color::Red))
+            .wrap(Wrap { trim: true }),
+        chunks[0],
+    );
+    chunks[1]
+}
+
+/// Like the warning banner, for notices that go away by themselves.
+fn draw_status_line(app: &App, frame: &mut Frame, area: Rect) -> Rect {
+    let Some(message) = app.get_status_message() else {
+        return area;
+    };
+
+    let text = format!("ℹ {}", message);
+    let height = calculate_text_height(&text, area.width).min(area.height);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+
+    frame.render_widget(
+        Paragraph::new(text)
+            .centered()
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: true }),
+        chunks[0],
+    );
+    chunks[1]
+}
+
+fn task_line(app: &App) -> String {
+    if app.is_editing_field() {
+        format!(
+            "Task: ❯ {} ❮ ('Enter' save | 'Esc' cancel)",
+            app.get_input_buffer()
+        )
+    } else {
+        match app.get_current_tag() {
+            Some(tag) => format!("Task: {}", tag),
+            None => "Task: none ('t' to set)".to_string(),
+        }
+    }
+}
+
+fn draw_goal_gauge(app: &App, frame: &mut Frame, area: Rect) {
+    let daily_goal = app.get_settings().daily_goal;
+    if daily_goal == 0 || area.height < 2 {
+        return;
+    }
+
+    let stats = app.get_stats();
+    let today = stats.get_today_sessions();
+    let streak = stats.get_current_streak(Local::now().date_naive(), daily_goal);
+    let width = area.width.min(50);
+    let gauge_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + 1,
+        width,
+        height: 1,
+    };
+
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+        .ratio((today as f64 / daily_goal as f64).min(1.0))
+        .label(format!(
+            "Daily goal: {}/{} | streak: {} days",
+            today, daily_goal, streak
+        ));
+    frame.render_widget(gauge, gauge_area);
 }
 
 fn draw_settings_screen(app: &App, frame: &mut Frame) {
@@ -192,9 +367,17 @@ fn draw_settings_screen(app: &App, frame: &mut Frame) {
     };
 
     let instructions_text = if app.is_editing_field() {
-        "✏ Editing: Enter numbers | 'Enter' to save | 'Esc' to cancel"
+        match app.get_settings_field() {
+            field if field.is_duration() => {
+                "✏ Editing: e.g. 25m, 90s, 1h30m or 1.5 (minutes) | 'Enter' to save | 'Esc' to cancel"
+            }
+            SettingsField::FlowtimeBreakRatio => {
+                "✏ Editing: e.g. 0.2 or 1/5 | 'Enter' to save | 'Esc' to cancel"
+            }
+            _ => "✏ Editing: Enter a number | 'Enter' to save | 'Esc' to cancel",
+        }
     } else {
-        "Navigation: ↑↓ to move | 'Enter' to edit | 'Esc' to return to timer"
+        "Navigation: ↑↓ to move | 'Enter' to edit or toggle | 'Esc' to return to timer"
     };
     let instructions_height =
         calculate_text_height(instructions_text, inner_area.width.saturating_sub(2));
@@ -203,7 +386,7 @@ fn draw_settings_screen(app: &App, frame: &mut Frame) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
-            Constraint::Min(8),
+            Constraint::Min(10),
             Constraint::Length(2),
             Constraint::Length(instructions_height + 2), // +2 for borders
         ])
@@ -223,27 +406,43 @@ fn draw_settings_screen(app: &App, frame: &mut Frame) {
     let current_field = app.get_settings_field();
     let editing = app.is_editing_field();
 
+    let settings_file = app.get_settings_file();
     let items: Vec<ListItem> = vec![
+        create_setting_item(
+            "Profile",
+            &format!(
+                "{} ({} defined, ←→ to switch)",
+                settings_file.active_profile().unwrap_or("none"),
+                settings_file.profiles.len()
+            ),
+            matches!(current_field, SettingsField::Profile),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
         create_setting_item(
             "Fokus Time",
-            &format!("{} minutes", settings.working_time),
+            &settings.working_time.to_string(),
             matches!(current_field, SettingsField::WorkingTime),
             editing,
             app.get_input_buffer(),
+            app.get_input_error(),
         ),
         create_setting_item(
             "Break Time",
-            &format!("{} minutes", settings.break_time),
+            &settings.break_time.to_string(),
             matches!(current_field, SettingsField::BreakTime),
             editing,
             app.get_input_buffer(),
+            app.get_input_error(),
         ),
         create_setting_item(
             "Long Break Time",
-            &format!("{} minutes", settings.long_break_time),
+            &settings.long_break_time.to_string(),
             matches!(current_field, SettingsField::LongBreakTime),
             editing,
             app.get_input_buffer(),
+            app.get_input_error(),
         ),
         create_setting_item(
             "Sessions Until Long Break",
@@ -251,6 +450,128 @@ fn draw_settings_screen(app: &App, frame: &mut Frame) {
             matches!(current_field, SettingsField::SessionsUntilLongBreak),
             editing,
             app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Daily Goal",
+            &match settings.daily_goal {
+                0 => "off".to_string(),
+                goal => format!("{} sessions", goal),
+            },
+            matches!(current_field, SettingsField::DailyGoal),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Skips Count Toward Long Break",
+            if settings.skips_advance_cycle {
+                "yes"
+            } else {
+                "no"
+            },
+            matches!(current_field, SettingsField::SkipsAdvanceCycle),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Apply Length Changes To",
+            &settings.live_apply.to_string(),
+            matches!(current_field, SettingsField::LiveApply),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "After Sleep",
+            &settings.suspend_policy.to_string(),
+            matches!(current_field, SettingsField::SuspendPolicy),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Auto-start Breaks",
+            if settings.auto_start_breaks {
+                "yes"
+            } else {
+                "no"
+            },
+            matches!(current_field, SettingsField::AutoStartBreaks),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Auto-start Fokus Sessions",
+            if settings.auto_start_work {
+                "yes"
+            } else {
+                "no"
+            },
+            matches!(current_field, SettingsField::AutoStartWork),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Auto-start After",
+            &settings.auto_start_delay.to_string(),
+            matches!(current_field, SettingsField::AutoStartDelay),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Stop After",
+            &match settings.stop_after_cycles {
+                0 => "off".to_string(),
+                cycles => format!("{} cycles", cycles),
+            },
+            matches!(current_field, SettingsField::StopAfterCycles),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Pause When Idle For",
+            &match settings.idle_timeout.as_secs() {
+                0 => "off".to_string(),
+                _ => settings.idle_timeout.to_string(),
+            },
+            matches!(current_field, SettingsField::IdleTimeout),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "When Back From Idle",
+            &settings.idle_return.to_string(),
+            matches!(current_field, SettingsField::IdleReturn),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Flowtime (Count Up)",
+            if settings.flowtime { "yes" } else { "no" },
+            matches!(current_field, SettingsField::Flowtime),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
+        ),
+        create_setting_item(
+            "Flowtime Break Ratio",
+            &format!(
+                "{} ({:.0}% of focused time)",
+                settings.flowtime_break_ratio,
+                settings.flowtime_break_ratio * 100.0
+            ),
+            matches!(current_field, SettingsField::FlowtimeBreakRatio),
+            editing,
+            app.get_input_buffer(),
+            app.get_input_error(),
         ),
     ];
 
@@ -261,15 +582,17 @@ fn draw_settings_screen(app: &App, frame: &mut Frame) {
                 .border_type(BorderType::Rounded),
         )
         .style(Style::default().fg(Color::White));
-    frame.render_widget(settings_list, chunks[1]);
-
-    if let Some(save_time) = app.settings_saved_message {
-        if save_time.elapsed().as_secs() < 2 {
-            let save_msg = Paragraph::new("✓ Settings saved!")
-                .alignment(Alignment::Center)
-                .style(Style::default().fg(Color::Green));
-            frame.render_widget(save_msg, chunks[2]);
-        }
+    // Selecting the current row scrolls it into view on short terminals
+    let mut list_state = ListState::default().with_selected(Some(current_field.row()));
+    frame.render_stateful_widget(settings_list, chunks[1], &mut list_state);
+
+    if let Some((save_time, message)) = &app.settings_saved_message
+        && save_time.elapsed().as_secs() < 3
+    {
+        let save_msg = Paragraph::new(message.as_str())
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::Green));
+        frame.render_widget(save_msg, chunks[2]);
     }
 
     let help = Paragraph::new(instructions_text)
@@ -284,12 +607,188 @@ fn draw_settings_screen(app: &App, frame: &mut Frame) {
     frame.render_widget(help, chunks[3]);
 }
 
+fn draw_stats_screen(app: &App, frame: &mut Frame) {
+    let title = Line::from("Stats").bold().green();
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Block::bordered()
+            .title(title)
+            .border_type(BorderType::Rounded),
+        area,
+    );
+
+    let inner_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+
+    let instructions_text = "'Tab' switch 7/30 days | 'Esc' to return to timer";
+    let instructions_height =
+        calculate_text_height(instructions_text, inner_area.width.saturating_sub(2));
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(6),
+            Constraint::Length(9),
+            Constraint::Length(instructions_height + 2), // +2 for borders
+        ])
+        .split(inner_area);
+
+    let stats = app.get_stats();
+    let range = app.get_stats_range();
+    let today = Local::now().date_naive();
+
+    let top_tasks = stats
+        .get_top_tasks(3)
+        .iter()
+        .map(|(tag, total)| {
+            format!(
+                "{} {} ({}h{:02}m)",
+                tag,
+                total.sessions,
+                total.focused_secs / 3600,
+                total.focused_secs / 60 % 60
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let header_text = format!(
+        "Total: {} ({}h{:02}m focused) | Today: {} | Avg/day ({}d): {:.1}\n\
+        Current streak: {} days | Longest streak: {} days | Goal streak: {} days\n\
+        Top tasks: {}",
+        stats.get_total_sessions(),
+        stats.get_focused_minutes() / 60,
+        stats.get_focused_minutes() % 60,
+        stats.get_today_sessions(),
+        range.days(),
+        stats.get_average_per_day(today, range.days()),
+        stats.get_current_streak(today, 1),
+        stats.get_longest_streak(1),
+        match app.get_settings().daily_goal {
+            0 => 0,
+            goal => stats.get_current_streak(today, goal),
+        },
+        if top_tasks.is_empty() {
+            "none yet"
+        } else {
+            &top_tasks
+        },
+    );
+    let header = Paragraph::new(header_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(header, chunks[0]);
+
+    let days = stats.get_sessions_for_range(today, range.days());
+    let bars: Vec<Bar> = days
+        .iter()
+        .map(|(date, count)| {
+            let label = match range {
+                StatsRange::Week => date.format("%a").to_string(),
+                StatsRange::Month => date.day().to_string(),
+            };
+            Bar::default()
+                .value(*count)
+                .label(Line::from(label))
+                .text_value(if *count > 0 {
+                    count.to_string()
+                } else {
+                    String::new()
+                })
+        })
+        .collect();
+
+    let chart_width = chunks[1].width.saturating_sub(2);
+    let bar_gap = 1;
+    let bar_width = (chart_width / days.len().max(1) as u16)
+        .saturating_sub(bar_gap)
+        .max(1);
+    let chart = BarChart::default()
+        .block(
+            Block::default()
+                .title(format!("Last {} days", range.days()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        )
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(bar_gap)
+        .bar_style(Style::default().fg(Color::Green))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Green));
+    frame.render_widget(chart, chunks[1]);
+
+    // GitHub-style heatmap: one column per week, Monday at the top
+    let weeks = (chunks[2].width.saturating_sub(2) as u64).min(53);
+    let last_monday = today
+        .checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64))
+        .unwrap_or(today);
+    let first_monday = last_monday
+        .checked_sub_days(Days::new(weeks.saturating_sub(1) * 7))
+        .unwrap_or(last_monday);
+
+    let heatmap: Vec<Line> = (0..7)
+        .map(|weekday| {
+            let cells: Vec<Span> = (0..weeks)
+                .map(|week| {
+                    let date = first_monday
+                        .checked_add_days(Days::new(week * 7 + weekday))
+                        .unwrap_or(first_monday);
+                    if date > today {
+                        Span::raw(" ")
+                    } else {
+                        Span::styled(
+                            "■",
+                            Style::default().fg(heatmap_color(stats.get_sessions_for_date(date))),
+                        )
+                    }
+                })
+                .collect();
+            Line::from(cells)
+        })
+        .collect();
+
+    let calendar = Paragraph::new(heatmap).alignment(Alignment::Center).block(
+        Block::default()
+            .title("Past year")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded),
+    );
+    frame.render_widget(calendar, chunks[2]);
+
+    let help = Paragraph::new(instructions_text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Gray))
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        );
+    frame.render_widget(help, chunks[3]);
+}
+
+fn heatmap_color(sessions: u64) -> Color {
+    match sessions {
+        0 => Color::DarkGray,
+        1..=2 => Color::Rgb(14, 68, 41),
+        3..=4 => Color::Rgb(0, 109, 50),
+        5..=7 => Color::Rgb(38, 166, 65),
+        _ => Color::Rgb(57, 211, 83),
+    }
+}
+
 fn create_setting_item(
     label: &str,
     value: &str,
     selected: bool,
     editing: bool,
     input_buffer: &str,
+    input_error: Option<&str>,
 ) -> ListItem<'static> {
     let display_value = if selected && editing {
         format!("  {}: ❯ {} ❮", label, input_buffer)
@@ -314,7 +813,17 @@ fn create_setting_item(
         Style::default().fg(Color::White)
     };
 
-    ListItem::new(Line::from(Span::styled(display_value, style)))
+    let mut lines = vec![Line::from(Span::styled(display_value, style))];
+    if selected
+        && editing
+        && let Some(error) = input_error
+    {
+        lines.push(Line::from(Span::styled(
+            format!("    ✗ {}", error),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    ListItem::new(lines)
 }
 
 fn calculate_text_height(text: &str, width: u16) -> u16 {
@@ -328,6 +837,6 @@ fn calculate_text_height(text: &str, width: u16) -> u16 {
     if total_chars <= chars_per_line {
         1
     } else {
-        ((total_chars + chars_per_line - 1) / chars_per_line) as u16
+        total_chars.div_ceil(chars_per_line) as u16
     }
 }